@@ -4,10 +4,289 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 use super::tokens::Token;
+use util::utf8::{Position, Span};
+
+/// Reference to a type as written in the source code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeRef {
+    Primitive(Token),   // one of the builtin type keywords, e.g. 'i32'
+    Named(Token),       // identifier naming a user defined type
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
     Binary{ lhs: Box<Expression>, operator: Token, rhs: Box<Expression> },
+    Assign{ target: Box<Expression>, operator: Token, value: Box<Expression> },
     Unary{ operator: Token, rhs: Box<Expression> },
+    Ref{ operator: Token, mutable: bool, expr: Box<Expression> },       // '&expr' or '&mut expr'
+    Deref{ operator: Token, expr: Box<Expression> },                    // '*expr'
+    Cast{ expr: Box<Expression>, target: TypeRef },
+    Try{ expr: Box<Expression> },                                       // 'expr?'
+    Call{ callee: Box<Expression>, args: Vec<Expression>, close: Position },   // close of the ')'
+    Member{ object: Box<Expression>, name: Token },                     // name is an identifier
+    Index{ object: Box<Expression>, index: Box<Expression>, close: Position }, // close of the ']'
+    Block{ open: Position, statements: Vec<Statement>, tail: Option<Box<Expression>>, close: Position },
+    If{ keyword: Position, cond: Box<Expression>, then_block: Box<Expression>, else_block: Option<Box<Expression>> },
+    Match{ keyword: Position, scrutinee: Box<Expression>, arms: Vec<(Pattern, Expression)>, close: Position },
+    Tuple{ open: Position, elements: Vec<Expression>, close: Position },  // '()' is the unit tuple
+    Literal(Token),
+    Identifier(Token),
+}
+
+/// Pattern of a match arm.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
     Literal(Token),
-}
\ No newline at end of file
+    Binding(Token),         // identifier bound to the matched value
+    Wildcard(Position),     // '_'
+}
+
+impl Pattern {
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Pattern::Literal(token) | Pattern::Binding(token) => token_span(token),
+            Pattern::Wildcard(pos) => Some( Span::new(*pos, *pos) ),
+        }
+    }
+}
+
+impl TypeRef {
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            TypeRef::Primitive(token) | TypeRef::Named(token) => token_span(token),
+        }
+    }
+}
+
+impl Expression {
+
+    /// Returns the range of source code covered by the tokens of the expression, including its
+    /// delimiters and keywords.
+    /// # Notes
+    /// Tokens which are not kept in the AST, e.g. the parentheses of a grouping, are not part of
+    /// the span.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Expression::Binary{lhs, rhs, ..} => join(lhs.span(), rhs.span()),
+            Expression::Assign{target, value, ..} => join(target.span(), value.span()),
+            Expression::Unary{operator, rhs: expr} | Expression::Ref{operator, expr, ..}
+            | Expression::Deref{operator, expr} => join(token_span(operator), expr.span()),
+            Expression::Cast{expr, target} => join(expr.span(), target.span()),
+            Expression::Try{expr} => expr.span(),
+            Expression::Call{callee, close, ..} | Expression::Index{object: callee, close, ..} =>
+                join(callee.span(), Some( Span::new(*close, *close) )),
+            Expression::Member{object, name} => join(object.span(), token_span(name)),
+            Expression::Block{open, close, ..} | Expression::Match{keyword: open, close, ..}
+            | Expression::Tuple{open, close, ..} => Some( Span::new(*open, *close) ),
+            Expression::If{keyword, then_block, else_block, ..} => {
+                let span = join(Some( Span::new(*keyword, *keyword) ), then_block.span());
+                join(span, else_block.as_ref().and_then(|block| block.span()))
+            },
+            Expression::Literal(token) | Expression::Identifier(token) => token_span(token),
+        }
+    }
+
+    /// Returns the source code of the expression's span, see `span()`, from the data it has been
+    /// parsed from.
+    pub fn source_span<'a>(&self, data: &'a [u8]) -> Option<&'a str> {
+        let range = self.span()?.byte_range(data)?;
+        std::str::from_utf8(&data[range]).ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Let{ name: Token, mutable: bool, type_ref: Option<TypeRef>, value: Option<Expression> },
+    Expr(Expression),
+    While{ cond: Expression, body: Expression },  // body is always a block expression
+    Loop{ body: Expression },                     // body is always a block, break values are only kept in the AST
+    For{ binding: Token, iter: Expression, body: Expression },  // binding is an identifier
+    Break(Token, Option<Expression>),             // value only within 'loop', see `Statement::Loop`
+    Continue(Token),
+    Return(Option<Expression>),
+    Expect{ condition: Expression, implication: Option<Expression>, message: Option<String> }, // 'cond => impl', message decoded
+}
+
+impl Statement {
+
+    /// Returns the range of source code covered by the tokens of the statement, leading keywords
+    /// and the terminating semicolon are only included if kept in the AST.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Statement::Let{name, type_ref, value, ..} => {
+                let span = join(token_span(name), type_ref.as_ref().and_then(|t| t.span()));
+                join(span, value.as_ref().and_then(|value| value.span()))
+            },
+            Statement::Expr(expr) => expr.span(),
+            Statement::While{cond, body} => join(cond.span(), body.span()),
+            Statement::Loop{body} => body.span(),
+            Statement::For{binding, iter, body} => join(join(token_span(binding), iter.span()), body.span()),
+            Statement::Break(token, value) => join(token_span(token), value.as_ref().and_then(|value| value.span())),
+            Statement::Continue(token) => token_span(token),
+            Statement::Return(value) => value.as_ref().and_then(|value| value.span()),
+            Statement::Expect{condition, implication, ..} =>
+                join(condition.span(), implication.as_ref().and_then(|expr| expr.span())),
+        }
+    }
+}
+
+fn token_span(token: &Token) -> Option<Span> {
+    Some( Span::new(token.position(), token.end_position()) )
+}
+
+/// Returns the smallest span covering both spans.
+fn join(a: Option<Span>, b: Option<Span>) -> Option<Span> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some( Span::new(a.start.min(b.start), a.end.max(b.end)) ),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// Transformation of an AST taking nodes by value and returning the rewritten ones, e.g. for
+/// constant folding. The default methods only rebuild the node from its folded children, an
+/// implementation overrides them and calls `fold_expression_children` or
+/// `fold_statement_children` to continue the recursion.
+pub trait Fold {
+
+    fn fold_expression(&mut self, expr: Expression) -> Expression {
+        fold_expression_children(self, expr)
+    }
+
+    fn fold_statement(&mut self, stmt: Statement) -> Statement {
+        fold_statement_children(self, stmt)
+    }
+}
+
+/// Rebuilds the expression with each of its child expressions and statements passed to `folder`.
+pub fn fold_expression_children<F: Fold + ?Sized>(folder: &mut F, expr: Expression) -> Expression {
+    let mut fold = |expr: Box<Expression>| Box::new(folder.fold_expression(*expr));
+    match expr {
+        Expression::Binary{lhs, operator, rhs} => Expression::Binary{ lhs: fold(lhs), operator, rhs: fold(rhs) },
+        Expression::Assign{target, operator, value} =>
+            Expression::Assign{ target: fold(target), operator, value: fold(value) },
+        Expression::Unary{operator, rhs} => Expression::Unary{ operator, rhs: fold(rhs) },
+        Expression::Ref{operator, mutable, expr} => Expression::Ref{ operator, mutable, expr: fold(expr) },
+        Expression::Deref{operator, expr} => Expression::Deref{ operator, expr: fold(expr) },
+        Expression::Cast{expr, target} => Expression::Cast{ expr: fold(expr), target },
+        Expression::Try{expr} => Expression::Try{ expr: fold(expr) },
+        Expression::Call{callee, args, close} => Expression::Call{ callee: fold(callee),
+            args: args.into_iter().map(|arg| folder.fold_expression(arg)).collect(), close },
+        Expression::Member{object, name} => Expression::Member{ object: fold(object), name },
+        Expression::Index{object, index, close} =>
+            Expression::Index{ object: fold(object), index: fold(index), close },
+        Expression::Block{open, statements, tail, close} => Expression::Block{ open,
+            statements: statements.into_iter().map(|stmt| folder.fold_statement(stmt)).collect(),
+            tail: tail.map(|tail| Box::new(folder.fold_expression(*tail))), close },
+        Expression::If{keyword, cond, then_block, else_block} => Expression::If{ keyword, cond: fold(cond),
+            then_block: fold(then_block), else_block: else_block.map(fold) },
+        Expression::Match{keyword, scrutinee, arms, close} => Expression::Match{ keyword, scrutinee: fold(scrutinee),
+            arms: arms.into_iter().map(|(pattern, value)| (pattern, folder.fold_expression(value))).collect(), close },
+        Expression::Tuple{open, elements, close} => Expression::Tuple{ open,
+            elements: elements.into_iter().map(|element| folder.fold_expression(element)).collect(), close },
+        Expression::Literal(_) | Expression::Identifier(_) => expr,
+    }
+}
+
+/// Rebuilds the statement with each of its child expressions passed to `folder`.
+pub fn fold_statement_children<F: Fold + ?Sized>(folder: &mut F, stmt: Statement) -> Statement {
+    match stmt {
+        Statement::Let{name, mutable, type_ref, value} =>
+            Statement::Let{ name, mutable, type_ref, value: value.map(|value| folder.fold_expression(value)) },
+        Statement::Expr(expr) => Statement::Expr(folder.fold_expression(expr)),
+        Statement::While{cond, body} =>
+            Statement::While{ cond: folder.fold_expression(cond), body: folder.fold_expression(body) },
+        Statement::Loop{body} => Statement::Loop{ body: folder.fold_expression(body) },
+        Statement::For{binding, iter, body} =>
+            Statement::For{ binding, iter: folder.fold_expression(iter), body: folder.fold_expression(body) },
+        Statement::Break(token, value) => Statement::Break(token, value.map(|value| folder.fold_expression(value))),
+        Statement::Return(value) => Statement::Return(value.map(|value| folder.fold_expression(value))),
+        Statement::Expect{condition, implication, message} => Statement::Expect{
+            condition: folder.fold_expression(condition),
+            implication: implication.map(|expr| folder.fold_expression(expr)), message },
+        Statement::Continue(_) => stmt,
+    }
+}
+
+/// Top level declaration within a TESIL file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub doc: Vec<String>,       // preceding comments, only collected if enabled in the parser
+    pub attributes: Vec<Attribute>,
+    pub kind: ItemKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]    // items are few, boxing the function body saves nothing
+pub enum ItemKind {
+    Struct{ name: Token, fields: Vec<Field> },
+    Function{ name: Token, params: Vec<Field>, return_type: Option<TypeRef>, body: Expression },
+}
+
+/// Outer attribute preceding an item, e.g. `#[cfg(test)]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub path: Vec<String>,      // path segments separated by '::'
+    pub args: Vec<Token>,       // tokens between the parentheses, empty without arguments
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: Token,
+    pub type_ref: TypeRef,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Replaces multiplications by the integer literal one with the other operand.
+    struct RemoveMulOne;
+
+    impl Fold for RemoveMulOne {
+        fn fold_expression(&mut self, expr: Expression) -> Expression {
+            match fold_expression_children(self, expr) {
+                Expression::Binary{lhs, operator: Token::Star(_), rhs}
+                    if std::matches!(*rhs, Expression::Literal(Token::Integer{value: 1, ..})) => *lhs,
+                expr => expr,
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold() {
+        let expr: Expression = "f(x * 1) + y * 1 * 1".parse().unwrap();
+        let expected: Expression = "f(x    ) + y".parse().unwrap();    // same positions
+        assert_eq!(RemoveMulOne.fold_expression(expr), expected);
+
+        let stmt = RemoveMulOne.fold_statement(Statement::Return(Some("x * 2".parse().unwrap())));
+        assert_eq!(stmt, Statement::Return(Some("x * 2".parse().unwrap())));
+    }
+
+    #[test]
+    fn test_source_span() {
+        let txt = "foo + bar";
+        let expr: Expression = txt.parse().unwrap();
+        assert_eq!(expr.source_span(txt.as_bytes()), Some("foo + bar"));
+
+        let txt = "{\n  let x = f(a,\n    b); x }";
+        let expr = txt.parse::<Expression>().unwrap();
+        assert_eq!(expr.source_span(txt.as_bytes()), Some(txt));
+        match expr {
+            Expression::Block{statements, ..} => match &statements[0] {
+                Statement::Let{value: Some(value), ..} =>
+                    assert_eq!(value.source_span(txt.as_bytes()), Some("f(a,\n    b)")),
+                stmt => panic!("unexpected {:?}", stmt),
+            },
+            expr => panic!("unexpected {:?}", expr),
+        }
+
+        for txt in ["&mut x", "*p", "if a { b } else if c { d }", "match x { 1 => a, _ => b }", "(a, (b))", "()", "{}", "x[i + 1]",
+                "f()", "if a { b }"] {
+            let expr: Expression = txt.parse().unwrap();
+            assert_eq!(expr.source_span(txt.as_bytes()), Some(txt));
+        }
+    }
+}
@@ -0,0 +1,196 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+use std::fmt::{Display, Formatter};
+use super::tokens::Token;
+use super::ast::Expression;
+
+/// Value of an evaluated constant expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    String(String),
+}
+
+/// Errors raised while evaluating an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    DivisionByZero,
+    TypeMismatch,
+    Overflow,
+    NotConstant,        // the expression cannot be evaluated at compile time
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Integer(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{:?}", v),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Char(v) => write!(f, "{}", v),
+            Value::String(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// Numeric operands of a binary operation after coercion: an integer combined with a float is
+/// converted to a float.
+enum Operands {
+    Integer(i64, i64),
+    Float(f64, f64),
+}
+
+impl Value {
+
+    fn operands(&self, rhs: &Value) -> Result<Operands, EvalError> {
+        match (self, rhs) {
+            (Value::Integer(a), Value::Integer(b)) => Ok( Operands::Integer(*a, *b) ),
+            (Value::Integer(a), Value::Float(b)) => Ok( Operands::Float(*a as f64, *b) ),
+            (Value::Float(a), Value::Integer(b)) => Ok( Operands::Float(*a, *b as f64) ),
+            (Value::Float(a), Value::Float(b)) => Ok( Operands::Float(*a, *b) ),
+            _ => Err( EvalError::TypeMismatch ),
+        }
+    }
+
+    pub fn add(&self, rhs: &Value) -> Result<Value, EvalError> {
+        match self.operands(rhs)? {
+            Operands::Integer(a, b) => a.checked_add(b).map(Value::Integer).ok_or(EvalError::Overflow),
+            Operands::Float(a, b) => Ok( Value::Float(a + b) ),
+        }
+    }
+
+    pub fn sub(&self, rhs: &Value) -> Result<Value, EvalError> {
+        match self.operands(rhs)? {
+            Operands::Integer(a, b) => a.checked_sub(b).map(Value::Integer).ok_or(EvalError::Overflow),
+            Operands::Float(a, b) => Ok( Value::Float(a - b) ),
+        }
+    }
+
+    pub fn mul(&self, rhs: &Value) -> Result<Value, EvalError> {
+        match self.operands(rhs)? {
+            Operands::Integer(a, b) => a.checked_mul(b).map(Value::Integer).ok_or(EvalError::Overflow),
+            Operands::Float(a, b) => Ok( Value::Float(a * b) ),
+        }
+    }
+
+    /// Divides the values, integer division truncates towards zero. Float division follows
+    /// IEEE 754, i.e. a division by zero yields an infinite value or NaN.
+    pub fn div(&self, rhs: &Value) -> Result<Value, EvalError> {
+        match self.operands(rhs)? {
+            Operands::Integer(_, 0) => Err( EvalError::DivisionByZero ),
+            Operands::Integer(a, b) => a.checked_div(b).map(Value::Integer).ok_or(EvalError::Overflow),
+            Operands::Float(a, b) => Ok( Value::Float(a / b) ),
+        }
+    }
+
+    /// Returns the remainder of the truncating division, it has the sign of the dividend.
+    pub fn rem(&self, rhs: &Value) -> Result<Value, EvalError> {
+        match self.operands(rhs)? {
+            Operands::Integer(_, 0) => Err( EvalError::DivisionByZero ),
+            Operands::Integer(a, b) => a.checked_rem(b).map(Value::Integer).ok_or(EvalError::Overflow),
+            Operands::Float(a, b) => Ok( Value::Float(a % b) ),
+        }
+    }
+
+    pub fn neg(&self) -> Result<Value, EvalError> {
+        match self {
+            Value::Integer(a) => a.checked_neg().map(Value::Integer).ok_or(EvalError::Overflow),
+            Value::Float(a) => Ok( Value::Float(-a) ),
+            _ => Err( EvalError::TypeMismatch ),
+        }
+    }
+}
+
+/// Evaluates a constant expression built from literals and arithmetic operators.
+pub fn evaluate(expr: &Expression) -> Result<Value, EvalError> {
+    match expr {
+        Expression::Literal(token) => literal(token),
+        Expression::Unary{operator: Token::Minus(_), rhs} => evaluate(rhs)?.neg(),
+        Expression::Unary{operator: Token::ExclamationMark(_), rhs} => match evaluate(rhs)? {
+            Value::Bool(v) => Ok( Value::Bool(!v) ),
+            _ => Err( EvalError::TypeMismatch ),
+        },
+        Expression::Binary{lhs, operator, rhs} => {
+            let lhs = evaluate(lhs)?;
+            let rhs = evaluate(rhs)?;
+            match operator {
+                Token::Plus(_) => lhs.add(&rhs),
+                Token::Minus(_) => lhs.sub(&rhs),
+                Token::Star(_) => lhs.mul(&rhs),
+                Token::Slash(_) => lhs.div(&rhs),
+                _ => Err( EvalError::NotConstant ),
+            }
+        },
+        _ => Err( EvalError::NotConstant ),
+    }
+}
+
+fn literal(token: &Token) -> Result<Value, EvalError> {
+    match token {
+        Token::Integer{value, negative: true, ..} => i128::try_from(*value).ok()
+            .and_then(|value| i64::try_from(-value).ok()).map(Value::Integer).ok_or(EvalError::Overflow),
+        Token::Integer{value, ..} => i64::try_from(*value).map(Value::Integer).map_err(|_| EvalError::Overflow),
+        Token::FloatNumber{value, ..} => Ok( Value::Float(*value) ),
+        Token::KwTrue(_) => Ok( Value::Bool(true) ),
+        Token::KwFalse(_) => Ok( Value::Bool(false) ),
+        Token::Char{ch, ..} => Ok( Value::Char(*ch) ),
+        Token::String{source, ..} => Ok( Value::String(source.clone()) ),
+        _ => Err( EvalError::NotConstant ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Parser;
+
+    fn eval(txt: &str) -> Result<Value, EvalError> {
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+        evaluate(&prs.expression().unwrap())
+    }
+
+    #[test]
+    fn test_division() {
+        assert_eq!(eval("5 / 2"), Ok( Value::Integer(2) ));
+        assert_eq!(eval("-5 / 2"), Ok( Value::Integer(-2) ));
+        assert_eq!(eval("5.0 / 2"), Ok( Value::Float(2.5) ));
+        assert_eq!(eval("5 / 0"), Err( EvalError::DivisionByZero ));
+        assert_eq!(Value::Integer(-5).rem(&Value::Integer(2)), Ok( Value::Integer(-1) ));
+        assert_eq!(Value::Integer(5).rem(&Value::Integer(0)), Err( EvalError::DivisionByZero ));
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(eval("9223372036854775807 + 1"), Err( EvalError::Overflow ));
+        assert_eq!(eval("9223372036854775808"), Err( EvalError::Overflow ));
+        assert_eq!(Value::Integer(i64::MIN).div(&Value::Integer(-1)), Err( EvalError::Overflow ));
+
+        let folded = |txt: &str| {
+            let mut prs = Parser::create(txt.to_string().into_bytes());
+            prs.set_fold_unary_minus_literals(true);
+            evaluate(&prs.expression().unwrap())
+        };
+        assert_eq!(folded("-9223372036854775808"), Ok( Value::Integer(i64::MIN) ));
+        assert_eq!(folded("-9223372036854775809"), Err( EvalError::Overflow ));
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        assert_eq!(eval("1 + true"), Err( EvalError::TypeMismatch ));
+        assert_eq!(eval("!1"), Err( EvalError::TypeMismatch ));
+        assert_eq!(eval("x + 1"), Err( EvalError::NotConstant ));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(eval("2 * 3 - 1").unwrap().to_string(), "5");
+        assert_eq!(eval("1 + 1.0").unwrap().to_string(), "2.0");
+        assert_eq!(eval("!true").unwrap().to_string(), "false");
+        assert_eq!(Value::Char('x').to_string(), "x");
+    }
+}
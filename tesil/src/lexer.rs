@@ -3,10 +3,13 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
+use std::collections::HashMap;
 use std::num::{ParseFloatError, ParseIntError};
+use std::rc::Rc;
 use std::str::FromStr;
 use super::tokens::{Token, IntegerBase};
-use util::utf8::{Stream, Position};
+use util::chars;
+use util::utf8::{Stream, Position, Span, LineEndingMode};
 
 /// Errors thrown by the lexical scanner while parsing the file.
 /// The scanner allows to 'look-ahead' one token using the `peek()` method. Tokens are consumed
@@ -16,45 +19,314 @@ pub enum LexerError {
     Unspecified,
     Utf8Error(Position),
     UnexpectedEndOfFile(Position),
+    UnterminatedString(Position),
+    NewlineInString(Position),
     Unexpected(Position, char),
+    InvalidEscape{ start: Position, ch: char },    // start is the position of the backslash
     InvalidEscapedUnicode(Position, String, u32),
     ExpectedDigit(Position),
     IntegerError(Position, String, ParseIntError),
     FloatError(Position, String, ParseFloatError),
+    IntegerTooLong(Position),   // position of the start of the literal
 }
 
+impl LexerError {
+
+    /// Returns the position in the source code the error refers to, `None` for `Unspecified`.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            LexerError::Unspecified => None,
+            LexerError::Utf8Error(pos)
+            | LexerError::UnexpectedEndOfFile(pos)
+            | LexerError::UnterminatedString(pos)
+            | LexerError::NewlineInString(pos)
+            | LexerError::Unexpected(pos, _)
+            | LexerError::InvalidEscape{start: pos, ..}
+            | LexerError::InvalidEscapedUnicode(pos, _, _)
+            | LexerError::ExpectedDigit(pos)
+            | LexerError::IntegerError(pos, _, _)
+            | LexerError::FloatError(pos, _, _)
+            | LexerError::IntegerTooLong(pos) => Some(*pos),
+        }
+    }
+
+    /// Returns the message describing the error to the user, without its position.
+    pub fn message(&self) -> String {
+        match self {
+            LexerError::Unspecified => "Unspecified lexical error.".to_string(),
+            LexerError::Utf8Error(_) => "Invalid UTF-8 encoding.".to_string(),
+            LexerError::UnexpectedEndOfFile(_) => "Unexpected end of file.".to_string(),
+            LexerError::UnterminatedString(_) => "Unterminated string literal.".to_string(),
+            LexerError::NewlineInString(_) => "Line break in string literal.".to_string(),
+            LexerError::Unexpected(_, ch) => format!("Unexpected character '{}'.", ch),
+            LexerError::InvalidEscape{ch, ..} => format!("Invalid escape sequence '\\{}'.", ch),
+            LexerError::InvalidEscapedUnicode(_, source, _) =>
+                format!("Invalid unicode escape sequence '\\u{{{}}}'.", source),
+            LexerError::ExpectedDigit(_) => "Expected digit.".to_string(),
+            LexerError::IntegerError(_, source, e) => format!("Invalid integer '{}': {}.", source, e),
+            LexerError::FloatError(_, source, e) => format!("Invalid float '{}': {}.", source, e),
+            LexerError::IntegerTooLong(_) => "Integer literal has too many digits.".to_string(),
+        }
+    }
+}
+
+/// Constructor of a keyword token from the position of the keyword.
+pub type KeywordToken = fn(Position) -> Token;
+
+/// Table of the reserved words of a TESIL dialect mapping each word to the constructor of its token.
+pub type Keywords = HashMap<&'static str, KeywordToken>;
+
+/// Default maximum number of digits of an integer literal, see `Lexer::set_max_integer_digits`.
+pub const DEFAULT_MAX_INTEGER_DIGITS: usize = 128;
+
 /// A lexical scanner for the TESIL langauge syntax.
-///
+/// Cloning a lexer takes a snapshot of its state, the source data itself is shared.
+#[derive(Clone)]
 pub struct Lexer {
     stream: Stream,
-    next: Result<Token, LexerError>
+    next: Option<Result<Token, LexerError>>,
+    strict_numbers: bool,
+    concat_strings: bool,
+    multiline_strings: bool,
+    underscore_wildcard: bool,
+    line_directives: bool,
+    file_name: Option<String>,
+    max_integer_digits: usize,
+    keywords: Rc<Keywords>,
 }
 
 impl Lexer {
 
     pub fn create(data: Vec<u8>) -> Lexer {
-        let mut lexer = Lexer { stream: Stream::create(data), next: Err(LexerError::Unspecified) };
-        lexer.next = lexer.scan();
-        lexer
+        Lexer::with_keywords(data, Lexer::default_keywords())
+    }
+
+    /// Creates a lexer for a dialect with its own set of reserved words. Words missing from the
+    /// table are scanned as identifiers.
+    /// # Notes
+    /// To extend or restrict the standard keywords modify the table of `Lexer::default_keywords()`,
+    /// e.g. insert `"fun"` mapped to `Token::KwFn` or remove `"type"`.
+    pub fn with_keywords(data: Vec<u8>, keywords: Keywords) -> Lexer {
+        Lexer { stream: Stream::create(data), next: None, strict_numbers: false, concat_strings: false,
+                multiline_strings: true, underscore_wildcard: false,
+                line_directives: false, file_name: None, max_integer_digits: DEFAULT_MAX_INTEGER_DIGITS,
+                keywords: Rc::new(keywords) }
+    }
+
+    /// Returns the keywords of standard TESIL.
+    pub fn default_keywords() -> Keywords {
+        let keywords: [(&'static str, KeywordToken); 33] = [
+            ("import",    Token::KwImport),
+            ("i8",        Token::KwTypeI8),
+            ("i16",       Token::KwTypeI16),
+            ("i32",       Token::KwTypeI32),
+            ("i64",       Token::KwTypeI64),
+            ("u8",        Token::KwTypeU8),
+            ("u16",       Token::KwTypeU16),
+            ("u32",       Token::KwTypeU32),
+            ("u64",       Token::KwTypeU64),
+            ("bool",      Token::KwTypeBool),
+            ("f32",       Token::KwTypeF32),
+            ("f64",       Token::KwTypeF64),
+            ("char",      Token::KwTypeChar),
+            ("fn",        Token::KwFn),
+            ("struct",    Token::KwStruct),
+            ("enum",      Token::KwEnum),
+            ("type",      Token::KwType),
+            ("break",     Token::KwBreak),
+            ("continue",  Token::KwContinue),
+            ("expect",    Token::KwExpect),
+            ("let",       Token::KwLet),
+            ("mut",       Token::KwMut),
+            ("true",      Token::KwTrue),
+            ("false",     Token::KwFalse),
+            ("as",        Token::KwAs),
+            ("if",        Token::KwIf),
+            ("else",      Token::KwElse),
+            ("while",     Token::KwWhile),
+            ("return",    Token::KwReturn),
+            ("match",     Token::KwMatch),
+            ("loop",      Token::KwLoop),
+            ("for",       Token::KwFor),
+            ("in",        Token::KwIn),
+        ];
+        keywords.into_iter().collect()
+    }
+
+    /// Enables or disables strict scanning of numbers. In strict mode a letter directly following
+    /// a numeric literal (e.g. `12abc`) is reported as `LexerError::Unexpected` instead of being
+    /// scanned as the start of an identifier. Strict mode is disabled by default.
+    pub fn set_strict_numbers(&mut self, strict: bool) {
+        self.strict_numbers = strict;
+    }
+
+    /// Enables or disables concatenation of adjacent string literals which are only separated by
+    /// whitespace, e.g. `"ab" "cd"` is scanned as the single string `"abcd"`. Disabled by default.
+    pub fn set_concat_strings(&mut self, concat: bool) {
+        self.concat_strings = concat;
+    }
+
+    /// Allows or forbids line breaks within string literals. If forbidden a line break in a string,
+    /// as defined by the line ending mode, is reported as `LexerError::NewlineInString`. Allowed by
+    /// default.
+    pub fn set_allow_multiline_strings(&mut self, allow: bool) {
+        self.multiline_strings = allow;
+    }
+
+    /// Sets the characters accepted as line break, for positions as well as for line breaks within
+    /// string literals. Defaults to `LineEndingMode::Unicode`.
+    pub fn set_line_ending_mode(&mut self, mode: LineEndingMode) {
+        self.stream.set_line_ending_mode(mode);
+    }
+
+    /// Enables scanning a standalone `_` as `Token::Underscore`, e.g. as wildcard of patterns.
+    /// Identifiers starting with or containing `_` are not affected. Disabled by default, `_` is
+    /// scanned as an identifier then.
+    pub fn set_underscore_wildcard(&mut self, enable: bool) {
+        self.underscore_wildcard = enable;
+    }
+
+    /// Enables line directives of generated sources, e.g. `#line 42 "file.tsl"`. A directive has
+    /// to start at the first column of a line and occupies the rest of it, the following line is
+    /// then reported as line 42. The optional file name is available from `file_name()`.
+    /// Disabled by default, `#` is always scanned as `Token::Hash` then.
+    pub fn set_line_directives(&mut self, enable: bool) {
+        self.line_directives = enable;
+    }
+
+    /// Returns the file name given by the last line directive, if any.
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Sets the maximum number of digits of an integer literal (digit separators and the base
+    /// prefix are not counted). Scanning stops with `LexerError::IntegerTooLong` as soon as a literal
+    /// exceeds the limit, so arbitrarily long digit runs are not accumulated. Defaults to
+    /// `DEFAULT_MAX_INTEGER_DIGITS`.
+    pub fn set_max_integer_digits(&mut self, max: usize) {
+        self.max_integer_digits = max;
+    }
+
+    /// Rewinds the lexer to the start of its source to scan it again, the options are kept.
+    pub fn reset(&mut self) {
+        self.stream.reset();
+        self.next = None;
+        self.file_name = None;
     }
 
     /// Returns the next found token or an LexerError without consuming it.
     /// Calling `peek()` several time consecutively or `get()` after `peek()` will always return
     /// the same result again.
-    pub fn peek(&self) -> Result<Token, LexerError> {
-        self.next.clone()
+    pub fn peek(&mut self) -> Result<Token, LexerError> {
+        if self.next.is_none() {
+            self.next = Some( self.scan() );
+        }
+        self.next.clone().unwrap()
     }
 
     /// Returns the next found token or an LexerError and consumes it (e.g. advances in the text).
     pub fn get(&mut self) -> Result<Token, LexerError> {
-        let r = self.next.clone();
-        self.next = self.scan();
-        r
+        match self.next.take() {
+            Some(r) => r,
+            None => self.scan(),
+        }
+    }
+
+    /// Recovers the lexer after an error so that scanning continues with the following token.
+    /// A pending look-ahead is dropped and the stream is resynchronized after an UTF-8 encoding
+    /// error. Other errors leave the stream behind the offending input already.
+    /// # Returns
+    /// Whether the stream had to be resynchronized.
+    pub fn recover(&mut self) -> bool {
+        self.next = None;
+        self.stream.resync()
+    }
+
+    /// Scans the remaining input and invokes `f` for every token up to (excluding) the end of file.
+    /// Scanning stops at the first error, which is returned.
+    pub fn for_each_token<F: FnMut(Token)>(mut self, mut f: F) -> Result<(), LexerError> {
+        loop {
+            match self.get()? {
+                Token::EndOfFile(_) => return Ok(()),
+                token => f(token),
+            }
+        }
+    }
+
+    /// Returns an iterator over the remaining tokens up to (excluding) the end of file which skips
+    /// comments. The iteration ends after the first error, which is yielded.
+    pub fn significant_tokens(mut self) -> impl Iterator<Item = Result<Token, LexerError>> {
+        let mut done = false;
+        std::iter::from_fn(move || {
+            while !done {
+                match self.get() {
+                    Ok(Token::EndOfFile(_)) => done = true,
+                    Ok(Token::Comment{..}) | Ok(Token::DocComment{..}) => {},
+                    Ok(token) => return Some(Ok(token)),
+                    Err(err) => {
+                        done = true;
+                        return Some(Err(err))
+                    },
+                }
+            }
+            None
+        })
+    }
+
+    /// Scans the remaining input up to (excluding) the end of file without stopping at errors, e.g.
+    /// for highlighting in an editor. Each error is reported as `Token::Error` at its position and
+    /// scanning continues behind the offending input.
+    pub fn tokenize_lossy(mut self) -> Vec<Token> {
+        let mut tokens = vec![];
+        loop {
+            let before = self.byte_position();
+            match self.get() {
+                Ok(Token::EndOfFile(_)) => return tokens,
+                Ok(token) => tokens.push(token),
+                Err(err) => {
+                    tokens.push(Token::Error{ pos: err.position().unwrap_or_default(), message: err.message() });
+                    if !self.recover() && self.byte_position() == before {
+                        let _ = self.stream.get();  // never stall on an error that consumed nothing
+                    }
+                },
+            }
+        }
+    }
+
+    /// Returns the source text from `start` to `end` (inclusive), e.g. the text of a token given by
+    /// its positions, without copying it.
+    /// # Returns
+    /// `None` if the positions do not refer to characters of the source or the text is not valid
+    /// UTF-8.
+    pub fn span_text(&self, start: Position, end: Position) -> Option<&str> {
+        let data = self.stream.data();
+        let range = Span::new(start, end).byte_range(data)?;
+        std::str::from_utf8(&data[range]).ok()
+    }
+
+    /// Rewinds the lexer and returns the tokens including comments starting on the given line, e.g.
+    /// to highlight a single line of an editor. Tokens spanning several lines are reported for the
+    /// line they start on. Afterwards the next token is the first one behind the line.
+    /// # Notes
+    /// An error in the source up to the end of the line is returned instead of the tokens.
+    pub fn tokens_in_line(&mut self, line: u32) -> Result<Vec<Token>, LexerError> {
+        self.reset();
+        let mut tokens = vec![];
+        loop {
+            match self.peek()? {
+                Token::EndOfFile(_) => return Ok(tokens),
+                token => match token.position() {
+                    pos if pos.line > line => return Ok(tokens),
+                    pos if pos.line == line => tokens.push(self.get()?),
+                    _ => { self.get()?; },
+                },
+            }
+        }
     }
 
     fn get_char(&mut self) -> Result< Option<char>, LexerError> {
         match self.stream.get() {
-            Err(()) => { return Err( LexerError::Utf8Error( self.pos() ) ) },
+            Err(()) => Err( LexerError::Utf8Error( self.pos() ) ),
             Ok(c) => Ok( c ),
         }
     }
@@ -63,14 +335,38 @@ impl Lexer {
         self.stream.pos()
     }
 
+    /// Returns the start of the token returned by the last `peek()`, or `pos()` if no token is
+    /// pending or the look-ahead is an error.
+    pub fn next_pos(&self) -> Position {
+        match &self.next {
+            Some( Ok(token) ) => token.position(),
+            _ => self.pos(),
+        }
+    }
+
+    /// Returns the byte offset within the source up to which the lexer has scanned, e.g. to stop
+    /// relexing at an unchanged region of an edited source.
+    /// # Notes
+    /// A token returned by `peek()` has already been scanned, so the offset lies behind it.
+    pub fn byte_position(&self) -> usize {
+        self.stream.byte_position()
+    }
+
+    /// Returns the number of bytes of the source behind `byte_position()`.
+    pub fn remaining_bytes(&self) -> usize {
+        self.stream.remaining_bytes()
+    }
+
     fn scan(&mut self) -> Result<Token, LexerError> {
         let ch = loop {
             let ch =   match self.get_char()? {
                 Some(c) => c,
-                None => return Ok( Token::EndOfFile ),
+                None => return Ok( Token::EndOfFile(self.pos()) ),
             };
             match ch {
-                ' ' | '\n' | '\t' => { continue; },
+                c if chars::is_whitespace(c) => { continue; },
+                // looping instead of recursing keeps long runs of directives off the stack
+                '#' if self.skip_line_directive()? => { continue; },
                 _ => break ch,
             }
         };
@@ -89,6 +385,9 @@ impl Lexer {
             ';' => Ok( Token::Semicolon(self.pos())),
             ',' => Ok( Token::Comma(self.pos())),
             '#' => Ok( Token::Hash(self.pos())),
+            '@' => Ok( Token::At(self.pos())),
+            '$' => Ok( Token::Dollar(self.pos())),
+            '?' => Ok( Token::Question(self.pos())),
             '!' => self.scan_exclamation_mark(),
             '<' => self.scan_less(),
             '>' => self.scan_greater(),
@@ -102,25 +401,46 @@ impl Lexer {
             '^' => self.scan_caret(),
             '.' => self.scan_dot(),
             ':' => self.scan_colon(),
-            '_' | 'a'..='z' | 'A'..='Z' => self.scan_identifier(ch),
+            c if chars::is_ident_start(c) => self.scan_identifier(ch),
             '\'' => self.scan_char_literal(),
             '"' => self.scan_string(),
-            '0'..='9' => self.scan_numbers(ch),
+            c if chars::is_digit(c) => {
+                let token = self.scan_numbers(ch)?;
+                if self.strict_numbers {
+                    self.check_number_end()?;
+                }
+                Ok( token )
+            },
             _ => Err( LexerError::Unexpected( self.pos(), ch ) )
         }
     }
 
+    /// Checks that a numeric literal is not directly followed by a letter.
+    fn check_number_end(&mut self) -> Result<(), LexerError> {
+        match self.stream.peek() {
+            Ok( Some(c) ) if chars::is_ident_start(c) => {
+                self.stream.advance();
+                Err( LexerError::Unexpected(self.pos(), c) )
+            },
+            _ => Ok(()),
+        }
+    }
+
     fn scan_numbers(&mut self, ch: char) -> Result<Token, LexerError> {
         let pos = self.pos();
         let mut str = vec![ch];
-        return match self.stream.peek() {
+        match self.stream.peek() {
             Ok( Some('x')) | Ok( Some('X')) if ch == '0' => {
-                str.push( self.stream.get().unwrap().unwrap() );
+                str.push( self.next_char()? );
                 self.scan_hex(str, pos)
             },
             Ok( Some('b')) | Ok( Some('B'))  if ch == '0' => {
-                str.push( self.stream.get().unwrap().unwrap() );
-                self.scan_binary(str, pos)
+                str.push( self.next_char()? );
+                self.scan_radix(str, pos, chars::is_bin_digit, IntegerBase::Binary)
+            }
+            Ok( Some('o')) | Ok( Some('O'))  if ch == '0' => {
+                str.push( self.next_char()? );
+                self.scan_radix(str, pos, chars::is_oct_digit, IntegerBase::Octal)
             }
             _ => {
                 self.scan_decimal(str, pos)
@@ -128,54 +448,120 @@ impl Lexer {
         }
     }
 
+    /// Consumes the character already looked at by a `peek()` of the stream.
+    fn next_char(&mut self) -> Result<char, LexerError> {
+        match self.stream.get() {
+            Ok( Some(c) ) => Ok(c),
+            Ok( None ) => Err( LexerError::UnexpectedEndOfFile(self.pos()) ),
+            Err(()) => Err( LexerError::Utf8Error(self.pos()) ),
+        }
+    }
+
+    /// Returns the error for a missing digit within a number, which is an encoding error if the
+    /// next character is not valid UTF-8.
+    fn expected_digit(&mut self) -> LexerError {
+        match self.stream.peek() {
+            Err(()) => LexerError::Utf8Error(self.pos()),
+            _ => LexerError::ExpectedDigit(self.pos()),
+        }
+    }
+
     fn scan_decimal(&mut self, mut source: Vec<char>, start: Position) -> Result<Token, LexerError> {
         let mut digits = source.clone();
         loop { // integer part
             let ch2 = match self.stream.peek() {
-                Err(()) | Ok( None ) => return Lexer::string_to_u64(
-                    digits.into_iter().collect(), source.into_iter().collect(), start,
-                    self.pos(), IntegerBase::Decimal),
+                Err(()) | Ok( None ) => break,
                 Ok( Some( c)) => c,
             };
             match ch2 {
-                '0'..='9' => {
+                c if chars::is_digit(c) => {
+                    self.check_digit_count(&digits, start)?;
                     self.stream.advance();
                     source.push(ch2);
                     digits.push(ch2);
                 },
                 '\'' => {
                     self.stream.advance();
+                    self.check_separator(&source, chars::is_digit)?;
                     source.push(ch2);
                 },
+                '.' if self.range_follows() => break,   // '0..10' is a range, not the float '0.'
                 '.' => {
+                    self.check_trailing_separator(&source)?;
                     self.stream.advance();
                     source.push(ch2);
                     digits.push(ch2);
                     return self.scan_fractional(start, source, digits)
                 },
                 'E' | 'e' => {
+                    self.check_trailing_separator(&source)?;
                     self.stream.advance();
                     digits.push(ch2);
                     source.push(ch2);
                     return self.scan_exponential_part(start, source, digits)
                 },
-                _ => return Lexer::string_to_u64(digits.into_iter().collect(),
-                                                 source.into_iter().collect(), start,
-                                                 self.pos(), IntegerBase::Decimal)
+                _ => break,
             }
         }
+        self.check_trailing_separator(&source)?;
+        Lexer::string_to_integer(digits.into_iter().collect(), source.into_iter().collect(), start,
+                             self.pos(), IntegerBase::Decimal)
+    }
+
+    /// Returns whether the next two characters are `..` without consuming them.
+    fn range_follows(&self) -> bool {
+        let mut ahead = self.stream.clone();
+        ahead.get() == Ok( Some('.') ) && ahead.get() == Ok( Some('.') )
+    }
+
+    /// Returns whether the next characters are a '.' followed by a hex digit or the 'p'/'P' of an
+    /// exponent without consuming them.
+    fn hex_fraction_follows(&self) -> bool {
+        let mut ahead = self.stream.clone();
+        ahead.get() == Ok( Some('.') )
+            && std::matches!(ahead.get(), Ok( Some( c ) ) if chars::is_hex_digit(c) || c == 'p' || c == 'P')
+    }
+
+    /// Checks that another digit may be appended to the already scanned digits of an integer.
+    fn check_digit_count(&self, digits: &[char], start: Position) -> Result<(), LexerError> {
+        if digits.len() >= self.max_integer_digits {
+            return Err(LexerError::IntegerTooLong(start))
+        }
+        Ok(())
+    }
+
+    /// Checks a digit separator that has just been consumed, it must directly follow a digit.
+    fn check_separator(&self, source: &[char], is_digit: fn(char) -> bool) -> Result<(), LexerError> {
+        match source.last() {
+            Some(c) if is_digit(*c) => Ok(()),
+            _ => Err(LexerError::Unexpected(self.pos(), '\'')),
+        }
+    }
+
+    /// Checks that a number does not end with a digit separator, i.e. the separator has been the
+    /// last consumed character.
+    fn check_trailing_separator(&self, source: &[char]) -> Result<(), LexerError> {
+        if source.last() == Some(&'\'') {
+            return Err(LexerError::Unexpected(self.pos(), '\''))
+        }
+        Ok(())
+    }
+
+    /// Returns the source of a number without its digit separators.
+    fn strip_separators(source: &str) -> String {
+        source.chars().filter(|c| *c != '\'').collect()
     }
 
-    fn string_to_u64(value: String, source: String, start: Position, end: Position, base: IntegerBase)
+    fn string_to_integer(value: String, source: String, start: Position, end: Position, base: IntegerBase)
             -> Result<Token, LexerError> {
         let base_value = match base {
             IntegerBase::Decimal => 10,
             IntegerBase::Hexadecimal => 16,
             IntegerBase::Binary => 2,
-            // IntegerBase::Octal => 8,
+            IntegerBase::Octal => 8,
         };
-        match u64::from_str_radix(value.as_ref(), base_value) {
-            Ok(v) => Ok( Token::Integer {start, end, source, value: v, base}),
+        match u128::from_str_radix(value.as_ref(), base_value) {
+            Ok(v) => Ok( Token::Integer {start, end, digits: Lexer::strip_separators(&source), source, value: v, base, negative: false}),
             Err(err) => Err( LexerError::IntegerError(start, value, err)),
         }
     }
@@ -183,7 +569,7 @@ impl Lexer {
     fn string_to_f64(value: String, source: String, start: Position, end: Position)
             -> Result<Token, LexerError> {
         match f64::from_str(value.as_ref()) {
-            Ok(v) => Ok( Token::FloatNumber {start, end, source, value: v}),
+            Ok(v) => Ok( Token::FloatNumber {start, end, digits: Lexer::strip_separators(&source), source, value: v}),
             Err(err) => Err( LexerError::FloatError(start, source, err) ),
         }
     }
@@ -191,32 +577,36 @@ impl Lexer {
     fn scan_fractional(&mut self, start: Position, mut source: Vec<char>, mut digits: Vec<char>) -> Result<Token, LexerError> {
         loop {
             let ch2 = match self.stream.peek() {
-                Err(()) | Ok(None) => return Lexer::string_to_f64(digits.into_iter().collect(),
-                                                                  source.into_iter().collect(), start, self.pos()),
+                Err(()) | Ok(None) => break,
                 Ok(Some(c)) => c,
             };
             match ch2 {
-                '0'..='9' => {
+                c if chars::is_digit(c) => {
                     self.stream.advance();
                     digits.push(ch2);
                     source.push(ch2);
                 },
                 '\'' => {
                     self.stream.advance();
+                    self.check_separator(&source, chars::is_digit)?;
                     source.push(ch2);
                 },
                 'E' | 'e' => {
+                    self.check_trailing_separator(&source)?;
                     self.stream.advance();
                     digits.push(ch2);
                     source.push(ch2);
                     return self.scan_exponential_part(start, source, digits)
                 },
-                _ => return Lexer::string_to_f64(digits.into_iter().collect(),
-                     source.into_iter().collect(), start,self.pos())
+                _ => break,
             }
         }
+        self.check_trailing_separator(&source)?;
+        Lexer::string_to_f64(digits.into_iter().collect(), source.into_iter().collect(), start, self.pos())
     }
 
+    /// Scans the exponent after the 'e'/'E' of a decimal float, i.e. an optional sign followed by
+    /// at least one digit. Used for both the integer (`1e5`) and the fractional (`1.5e5`) form.
     fn scan_exponential_part(&mut self, start: Position, mut source: Vec<char>, mut digits: Vec<char>)
             -> Result<Token, LexerError> {
         let mut sign_allowed = true;
@@ -236,31 +626,41 @@ impl Lexer {
                     source.push(ch);
                     sign_allowed = false;
                 },
-                '0'..='9' => {
+                c if chars::is_digit(c) => {
                     self.stream.advance();
                     sign_allowed = false;
                     digits.push(ch);
                     source.push(ch);
                     one_digit = true;
                 },
+                '\'' => {
+                    self.stream.advance();
+                    self.check_separator(&source, chars::is_digit)?;
+                    source.push(ch);
+                },
                 _ => {
                     break;
                 }
             }
         }
+        self.check_trailing_separator(&source)?;
         if !one_digit {
-            return Err( LexerError::ExpectedDigit(self.pos()))
+            return Err( self.expected_digit() )
         }
         let str: String = digits.into_iter().collect();
         match f64::from_str(&str) {
             Err( err ) => Err( LexerError::FloatError(start,
                                                       source.into_iter().collect(), err)),
-            Ok( value ) => Ok( Token::FloatNumber {start, end: self.pos(),
-                                                        source: source.into_iter().collect(), value}),
+            Ok( value ) => {
+                let source: String = source.into_iter().collect();
+                Ok( Token::FloatNumber {start, end: self.pos(), digits: Lexer::strip_separators(&source), source, value})
+            },
         }
     }
 
-    fn scan_binary(&mut self, mut source: Vec<char>, start: Position) -> Result<Token, LexerError> {
+    /// Scans the digits of a binary or octal integer after its base prefix.
+    fn scan_radix(&mut self, mut source: Vec<char>, start: Position, is_digit: fn(char) -> bool,
+            base: IntegerBase) -> Result<Token, LexerError> {
         let mut digits = vec![];
         loop {
             let ch = match self.stream.peek() {
@@ -268,23 +668,26 @@ impl Lexer {
                 Ok( Some( ch ) ) => ch,
             };
             match ch {
-                '0' | '1' => {
+                c if is_digit(c) => {
+                    self.check_digit_count(&digits, start)?;
                     self.stream.advance();
                     source.push(ch);
                     digits.push(ch);
                 },
                 '\'' => {
                     self.stream.advance();
+                    self.check_separator(&source, is_digit)?;
                     source.push(ch);
                 }
                 _ => break,
             }
         }
         if digits.is_empty() {
-            return Err( LexerError::ExpectedDigit(self.pos()));
+            return Err( self.expected_digit() );
         }
-        Lexer::string_to_u64(digits.into_iter().collect(), source.into_iter().collect(),
-                start, self.pos(), IntegerBase::Binary)
+        self.check_trailing_separator(&source)?;
+        Lexer::string_to_integer(digits.into_iter().collect(), source.into_iter().collect(),
+                start, self.pos(), base)
     }
 
     fn scan_hex(&mut self, mut source: Vec<char>, start: Position) -> Result<Token, LexerError> {
@@ -295,79 +698,203 @@ impl Lexer {
                 Ok( Some( ch ) ) => ch,
             };
             match ch {
-                '0'..='9' | 'a'..='f' | 'A'..='F' => {
+                c if chars::is_hex_digit(c) => {
+                    self.check_digit_count(&digits, start)?;
                     self.stream.advance();
                     source.push(ch);
                     digits.push(ch);
                 },
                 '\'' => {
                     self.stream.advance();
+                    self.check_separator(&source, chars::is_hex_digit)?;
                     source.push(ch);
                 }
+                // like in C a hex float needs fraction digits or an exponent, '0xF.' is the integer
+                // followed by a dot and '0x0..0x10' a range
+                '.' if !digits.is_empty() && self.hex_fraction_follows() => {
+                    self.check_trailing_separator(&source)?;
+                    self.stream.advance();
+                    source.push(ch);
+                    return self.scan_hex_float(start, source, digits, true)
+                },
+                'p' | 'P' if !digits.is_empty() => {
+                    self.check_trailing_separator(&source)?;
+                    self.stream.advance();
+                    source.push(ch);
+                    return self.scan_hex_float(start, source, digits, false)
+                },
                 _ => break,
             }
         }
         if digits.is_empty() {
-            return Err(LexerError::ExpectedDigit(self.pos()));
+            return Err( self.expected_digit() );
         }
-        Lexer::string_to_u64(digits.into_iter().collect(), source.into_iter().collect(),
+        self.check_trailing_separator(&source)?;
+        Lexer::string_to_integer(digits.into_iter().collect(), source.into_iter().collect(),
                 start, self.pos(), IntegerBase::Hexadecimal)
     }
 
+    /// Scans the remainder of a hexadecimal float like `0x1.8p3` after the '.' (`fraction` is true)
+    /// or the 'p'/'P' has been consumed. The mantissa is hexadecimal, the optional exponent after
+    /// the 'p' is decimal and to the base of 2.
+    fn scan_hex_float(&mut self, start: Position, mut source: Vec<char>, mut digits: Vec<char>,
+            fraction: bool) -> Result<Token, LexerError> {
+        let mut fraction_digits = 0;
+        let mut has_exponent = !fraction;
+        if fraction {
+            loop {
+                let ch = match self.stream.peek() {
+                    Err( () ) | Ok( None ) => break,
+                    Ok( Some( ch ) ) => ch,
+                };
+                match ch {
+                    c if chars::is_hex_digit(c) => {
+                        self.stream.advance();
+                        source.push(ch);
+                        digits.push(ch);
+                        fraction_digits += 1;
+                    },
+                    '\'' => {
+                        self.stream.advance();
+                        self.check_separator(&source, chars::is_hex_digit)?;
+                        source.push(ch);
+                    },
+                    'p' | 'P' => {
+                        self.check_trailing_separator(&source)?;
+                        self.stream.advance();
+                        source.push(ch);
+                        has_exponent = true;
+                        break;
+                    },
+                    _ => break,
+                }
+            }
+            self.check_trailing_separator(&source)?;
+        }
+        let mut exponent: i32 = 0;
+        if has_exponent {
+            let mut negative = false;
+            if let Ok( Some( ch @ ('+' | '-') ) ) = self.stream.peek() {
+                self.stream.advance();
+                source.push(ch);
+                negative = ch == '-';
+            }
+            let mut one_digit = false;
+            while let Ok( Some( ch @ '0'..='9' ) ) = self.stream.peek() {
+                self.stream.advance();
+                source.push(ch);
+                exponent = exponent.saturating_mul(10).saturating_add(Lexer::hex_digit_2_value(ch) as i32);
+                one_digit = true;
+            }
+            if !one_digit {
+                return Err( self.expected_digit() )
+            }
+            if negative {
+                exponent = -exponent;
+            }
+        }
+        let mantissa = digits.into_iter().fold(0.0, |v, c| v * 16.0 + Lexer::hex_digit_2_value(c) as f64);
+        let value = mantissa * 2f64.powi(exponent.saturating_sub(4 * fraction_digits));
+        let source: String = source.into_iter().collect();
+        Ok( Token::FloatNumber { start, end: self.pos(), digits: Lexer::strip_separators(&source), source, value } )
+    }
+
     fn scan_string(&mut self) -> Result<Token, LexerError> {
         let start = self.pos();
+        let mut segment_start = start;
         let mut str = vec![];
         loop {
+            let pos = self.pos().advanced(1);
             match self.stream.get() {
                 Err(()) => return Err( LexerError::Utf8Error(self.pos()) ),
-                Ok( None ) => return Err( LexerError::UnexpectedEndOfFile(self.pos()) ),
-                Ok( Some('"') ) => break,
+                Ok( None ) => return Err( LexerError::UnterminatedString(segment_start) ),
+                Ok( Some('"') ) => {
+                    let end = self.pos();
+                    if self.concat_strings && self.skip_to_string() {
+                        segment_start = self.pos();
+                        continue;
+                    }
+                    return Ok( Token::String{ start, end, source: str.into_iter().collect() } )
+                },
                 Ok( Some('\\') ) => {
-                    let ec = self.scan_escaped_char()?;
-                    str.push(ec);
+                    let ec = match self.scan_escaped_char() {
+                        Err( LexerError::UnexpectedEndOfFile(_) ) =>
+                            return Err( LexerError::UnterminatedString(start) ),
+                        r => r?,
+                    };
+                    str.extend(ec);
                 },
+                // line breaks within the literal are stored as '\n' independent of the platform
+                Ok( Some('\r') ) if self.stream.peek() == Ok( Some('\n') ) => {},
+                Ok( Some(c) ) if !self.multiline_strings && self.stream.line_ending_mode().is_line_break(c) =>
+                    return Err( LexerError::NewlineInString(pos) ),
                 Ok( Some(c) ) => str.push(c),
             }
         }
-        Ok( Token::String{ start, end: self.pos(), source: str.into_iter().collect() } )
+    }
+
+    /// Skips whitespace and consumes the opening quote of a following string literal if there is
+    /// one. The skipped whitespace would be ignored by the next scan anyway.
+    fn skip_to_string(&mut self) -> bool {
+        loop {
+            match self.stream.peek() {
+                Ok( Some(c) ) if chars::is_whitespace(c) => self.stream.advance(),
+                Ok( Some('"') ) => {
+                    self.stream.advance();
+                    return true
+                },
+                _ => return false,
+            }
+        }
     }
 
     fn scan_char_literal(&mut self) -> Result<Token, LexerError> {
         let start = self.pos();
-        return match self.stream.get() {
+        match self.stream.get() {
             Err(_) => Err(LexerError::Utf8Error(start)),
             Ok(None) => Err(LexerError::UnexpectedEndOfFile(start)),
             Ok(Some('\\')) => {
-                let ec = self.scan_escaped_char()?;
+                let ec = match self.scan_escaped_char()? {
+                    Some(ec) => ec,
+                    None => return Err(LexerError::InvalidEscape{ start: start.advanced(1), ch: '\n' }),
+                };
                 self.check_for_char('\'')?;
-                return Ok(Token::Char { start, ch: ec })
+                Ok(Token::Char { start, ch: ec })
             },
             Ok(Some(c)) => {
                 self.check_for_char('\'')?;
-                return Ok(Token::Char { start, ch: c })
+                Ok(Token::Char { start, ch: c })
             }
         }
     }
 
-    fn scan_escaped_char(&mut self) -> Result<char, LexerError> {
+    /// Scans an escape sequence after the backslash. A backslash directly followed by a line break
+    /// continues a string on the next line, the line break is dropped and `None` returned.
+    fn scan_escaped_char(&mut self) -> Result<Option<char>, LexerError> {
+        let start = self.pos();
         match self.stream.get() {
             Err( () ) => return Err( LexerError::Utf8Error(self.pos())),
             Ok( None ) => return Err( LexerError::UnexpectedEndOfFile(self.pos())),
-            Ok( Some('n') ) => return Ok( '\n' ),
-            Ok( Some('t') ) => return Ok( '\t' ),
-            Ok( Some('r') ) => return Ok( '\r' ),
-            Ok( Some('\\') ) => return Ok( '\\' ),
-            Ok( Some('\'') ) => return Ok( '\'' ),
-            Ok( Some('"') ) => return Ok( '"' ),
+            Ok( Some('n') ) => return Ok( Some('\n') ),
+            Ok( Some('t') ) => return Ok( Some('\t') ),
+            Ok( Some('r') ) => return Ok( Some('\r') ),
+            Ok( Some('\\') ) => return Ok( Some('\\') ),
+            Ok( Some('\'') ) => return Ok( Some('\'') ),
+            Ok( Some('"') ) => return Ok( Some('"') ),
+            Ok( Some('\r') ) if self.stream.peek() == Ok( Some('\n') ) => {
+                self.stream.advance();
+                return Ok( None )
+            },
+            Ok( Some(c) ) if chars::is_line_break(c) => return Ok( None ),
             Ok( Some('u')) | Ok( Some('U')) => {},
-            Ok( Some(c) ) => return Err( LexerError::Unexpected(self.pos(), c)),
+            Ok( Some(c) ) => return Err( LexerError::InvalidEscape{ start, ch: c }),
         };
         self.check_for_char('{')?;
         let unicode_start = self.pos();
         let unicode = self.scan_hex_digits(4)?;
         self.check_for_char('}')?;
         if let Some(uc) = char::from_u32(unicode.0 ) {
-            return Ok( uc )
+            return Ok( Some(uc) )
         }
         Err( LexerError::InvalidEscapedUnicode(unicode_start, unicode.1, unicode.0 ))
     }
@@ -382,7 +909,7 @@ impl Lexer {
                 Ok(Some(c)) => c,
             };
             match ch {
-                '0'..='9' | 'a'..='f' | 'A'..='F' => {
+                c if chars::is_hex_digit(c) => {
                     self.stream.advance();
                     str.push(ch);
                     value = (value << 4) + Lexer::hex_digit_2_value(ch);
@@ -404,7 +931,7 @@ impl Lexer {
     }
 
     fn check_for_char(&mut self, ch: char) -> Result<(), LexerError> {
-        return match self.stream.get() {
+        match self.stream.get() {
             Err(()) => Err(LexerError::Utf8Error(self.pos())),
             Ok(None) => Err(LexerError::UnexpectedEndOfFile(self.pos())),
             Ok(Some(c)) => {
@@ -413,7 +940,7 @@ impl Lexer {
                 }
                 Err(LexerError::Unexpected(self.pos(), c))
             },
-        };
+        }
     }
 
     fn scan_identifier(&mut self, ch: char) -> Result<Token, LexerError> {
@@ -426,41 +953,93 @@ impl Lexer {
                 Ok(Some(c)) => c,
             };
             match next_char {
-                '_' | 'a'..='z' | 'A'..='Z' | '0'..='9' => {
+                c if chars::is_ident_continue(c) => {
                     self.stream.advance();
-                    v.push(next_char);
+                    v.push(c);
                 },
                 _ => break,
             }
         }
         let str : String = v.into_iter().collect();
-        match str.as_ref() {
-            "import"    => Ok( Token::KwImport(start) ),
-            "i8"        => Ok( Token::KwTypeI8(start) ),
-            "i16"       => Ok( Token::KwTypeI16(start) ),
-            "i32"       => Ok( Token::KwTypeI32(start) ),
-            "i64"       => Ok( Token::KwTypeI64(start) ),
-            "u8"        => Ok( Token::KwTypeU8(start) ),
-            "u16"       => Ok( Token::KwTypeU16(start) ),
-            "u32"       => Ok( Token::KwTypeU32(start) ),
-            "u64"       => Ok( Token::KwTypeU64(start) ),
-            "bool"      => Ok( Token::KwTypeBool(start) ),
-            "f32"       => Ok( Token::KwTypeF32(start) ),
-            "f64"       => Ok( Token::KwTypeF64(start) ),
-            "char"      => Ok( Token::KwTypeChar(start) ),
-            "fn"        => Ok( Token::KwFn(start) ),
-            "struct"    => Ok( Token::KwStruct(start) ),
-            "enum"      => Ok( Token::KwEnum(start) ),
-            "type"      => Ok( Token::KwType(start) ),
-            "break"     => Ok( Token::KwBreak(start) ),
-            "continue"  => Ok( Token::KwContinue(start) ),
-            "expect"    => Ok( Token::KwExpect(start) ),
-            "let"       => Ok( Token::KwLet(start) ),
-            "mut"       => Ok( Token::KwMut(start) ),
-            "true"      => Ok( Token::KwTrue(start) ),
-            "false"     => Ok( Token::KwFalse(start) ),
-            _           => Ok( Token::Identifier {start, source: str, end: self.pos() })
+        if self.underscore_wildcard && str == "_" {
+            return Ok( Token::Underscore(start) )
+        }
+        match self.keywords.get(str.as_str()) {
+            Some(keyword) => Ok( keyword(start) ),
+            None => Ok( Token::Identifier {start, source: str, end: self.pos() }),
+        }
+    }
+
+    /// Consumes a line directive after a `#` at the start of a line if enabled.
+    /// # Returns
+    /// Whether a directive has been consumed, otherwise the `#` is a token of its own.
+    fn skip_line_directive(&mut self) -> Result<bool, LexerError> {
+        if self.line_directives && self.pos().column == 1 {
+            let snapshot = self.stream.clone();
+            if self.scan_line_directive()? {
+                return Ok( true )
+            }
+            self.stream = snapshot;
         }
+        Ok( false )
+    }
+
+    /// Scans the remainder of a line directive after the `#` and adjusts the line of the stream.
+    /// # Returns
+    /// Whether the input is a line directive, otherwise the stream has to be restored by the caller.
+    fn scan_line_directive(&mut self) -> Result<bool, LexerError> {
+        self.skip_blanks();
+        for expected in "line".chars() {
+            if self.stream.get() != Ok( Some(expected) ) {
+                return Ok( false )
+            }
+        }
+        if !self.skip_blanks() {
+            return Ok( false )
+        }
+        let mut line: Option<u32> = None;
+        while let Ok( Some( ch @ '0'..='9' ) ) = self.stream.peek() {
+            self.stream.advance();
+            let digit = ch.to_digit(10).unwrap();
+            match line.unwrap_or(0).checked_mul(10).and_then(|l| l.checked_add(digit)) {
+                Some(l) => line = Some(l),
+                None => return Ok( false ),
+            }
+        }
+        let line = match line {
+            Some(line) => line,
+            None => return Ok( false ),
+        };
+        self.skip_blanks();
+        let mut file_name = None;
+        if self.stream.peek() == Ok( Some('"') ) {
+            self.stream.advance();
+            if let Token::String{source, ..} = self.scan_string()? {
+                file_name = Some(source);
+            }
+            self.skip_blanks();
+        }
+        match self.stream.get() {
+            Ok( None ) => {},
+            Ok( Some('\r') ) if self.stream.peek() == Ok( Some('\n') ) => self.stream.advance(),
+            Ok( Some(c) ) if self.stream.line_ending_mode().is_line_break(c) => {},
+            _ => return Ok( false ),
+        }
+        self.stream.set_line(line);
+        if file_name.is_some() {
+            self.file_name = file_name;
+        }
+        Ok( true )
+    }
+
+    /// Skips spaces and tabs and returns whether any has been skipped.
+    fn skip_blanks(&mut self) -> bool {
+        let mut skipped = false;
+        while let Ok( Some(' ' | '\t') ) = self.stream.peek() {
+            self.stream.advance();
+            skipped = true;
+        }
+        skipped
     }
 
     fn scan_exclamation_mark(&mut self) -> Result<Token, LexerError> {
@@ -557,6 +1136,10 @@ impl Lexer {
                         },
                     }
                 }
+                // exactly three slashes start a doc comment, four or more are a plain comment
+                if str.first() == Some(&'/') && str.get(1) != Some(&'/') {
+                    return Ok(Token::DocComment{start: pos, comment: str[1..].iter().collect()})
+                }
                 Ok(Token::Comment{start: pos, comment: str.into_iter().collect()})
             },
             _ => Ok(Token::Slash(pos))
@@ -624,6 +1207,10 @@ impl Lexer {
             },
             Ok( Some('>')) => {
                 self.stream.advance();
+                if let Ok( Some('=')) = self.stream.peek() {
+                    self.stream.advance();
+                    return Ok( Token::ShrAssign(pos))
+                }
                 Ok( Token::ShiftRight(pos))
             },
             _ => Ok(Token::Greater(pos)),
@@ -643,6 +1230,10 @@ impl Lexer {
             },
             Ok( Some( '<' )) => {
                 self.stream.advance();
+                if let Ok( Some('=')) = self.stream.peek() {
+                    self.stream.advance();
+                    return Ok( Token::ShlAssign(pos))
+                }
                 Ok( Token::ShiftLeft(pos))
             }
             _ => Ok( Token::Less(pos)),
@@ -653,6 +1244,7 @@ impl Lexer {
 #[cfg(test)]
 mod test {
     use super::*;
+    use super::super::tokens::TokenKind;
 
     #[test]
     fn test_float_with_exp() {
@@ -660,7 +1252,27 @@ mod test {
         let mut lxr = Lexer::create(txt.to_string().into_bytes());
 
         assert_eq!(lxr.get(), Ok( Token::FloatNumber {start: Position{ line: 1, column: 1},
-            end: Position{ line: 1, column: 3}, source: "1e6".to_string(), value: 1e6}));
+            end: Position{ line: 1, column: 3}, source: "1e6".to_string(), digits: "1e6".to_string(), value: 1e6}));
+    }
+
+    #[test]
+    fn test_float_exp_edge_cases() {
+        let mut lxr = Lexer::create("1e".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::ExpectedDigit(Position{ line: 1, column: 2})));
+
+        let mut lxr = Lexer::create("1e+ ".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::ExpectedDigit(Position{ line: 1, column: 3})));
+
+        let mut lxr = Lexer::create("2.5E-".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::ExpectedDigit(Position{ line: 1, column: 5})));
+
+        let mut lxr = Lexer::create("1.e5 1E-3 7e+2".to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::FloatNumber {start: Position{ line: 1, column: 1},
+            end: Position{ line: 1, column: 4}, source: "1.e5".to_string(), digits: "1.e5".to_string(), value: 1e5}));
+        assert_eq!(lxr.get(), Ok( Token::FloatNumber {start: Position{ line: 1, column: 6},
+            end: Position{ line: 1, column: 9}, source: "1E-3".to_string(), digits: "1E-3".to_string(), value: 1e-3}));
+        assert_eq!(lxr.get(), Ok( Token::FloatNumber {start: Position{ line: 1, column: 11},
+            end: Position{ line: 1, column: 14}, source: "7e+2".to_string(), digits: "7e+2".to_string(), value: 700.0}));
     }
 
     #[test]
@@ -669,67 +1281,306 @@ mod test {
         let mut lxr = Lexer::create(txt.to_string().into_bytes());
 
         assert_eq!(lxr.get(), Ok( Token::FloatNumber {start: Position{ line: 1, column: 1},
-            end: Position{ line: 1, column: 3}, source: "0.1".to_string(), value: 0.1 }));
+            end: Position{ line: 1, column: 3}, source: "0.1".to_string(), digits: "0.1".to_string(), value: 0.1 }));
         assert_eq!(lxr.get(), Ok( Token::FloatNumber {start: Position{ line: 1, column: 5},
-            end: Position{ line: 1, column: 12}, source: "129.9011".to_string(), value: 129.9011}));
+            end: Position{ line: 1, column: 12}, source: "129.9011".to_string(), digits: "129.9011".to_string(), value: 129.9011}));
         assert_eq!(lxr.get(), Ok( Token::FloatNumber {start: Position{ line: 1, column: 14},
-            end: Position{ line: 1, column: 20}, source: "2'001.4".to_string(), value: 2001.4}));
+            end: Position{ line: 1, column: 20}, source: "2'001.4".to_string(), digits: "2001.4".to_string(), value: 2001.4}));
+    }
+
+    #[test]
+    fn test_float_exact_digits() {
+        let txt = "0.1 3.14159265358979323846";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+
+        match lxr.get() {
+            Ok( Token::FloatNumber{digits, value, ..} ) => {
+                assert_eq!(digits, "0.1");
+                assert_eq!(value, 0.1);
+            },
+            r => panic!("unexpected {:?}", r),
+        }
+        match lxr.get() {
+            Ok( Token::FloatNumber{digits, value, ..} ) => {
+                assert_eq!(digits, "3.14159265358979323846");
+                assert_eq!(value, std::f64::consts::PI);
+            },
+            r => panic!("unexpected {:?}", r),
+        }
     }
 
     #[test]
     fn test_integer_decimal() {
-        let txt = concat!("0 22 100'0001 9091");
+        let txt = "0 22 100'0001 9091";
         let mut lxr = Lexer::create(txt.to_string().into_bytes());
 
         assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 1},
-            end: Position{line: 1, column: 1}, source: "0".to_string(), value: 0, base: IntegerBase::Decimal}));
+            end: Position{line: 1, column: 1}, source: "0".to_string(), digits: "0".to_string(), value: 0, base: IntegerBase::Decimal, negative: false}));
         assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 3},
-            end: Position{line: 1, column: 4}, source: "22".to_string(), value: 22, base: IntegerBase::Decimal}));
+            end: Position{line: 1, column: 4}, source: "22".to_string(), digits: "22".to_string(), value: 22, base: IntegerBase::Decimal, negative: false}));
         assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 6},
-            end: Position{line: 1, column: 13}, source: "100'0001".to_string(), value: 1000001, base: IntegerBase::Decimal}));
+            end: Position{line: 1, column: 13}, source: "100'0001".to_string(), digits: "1000001".to_string(), value: 1000001, base: IntegerBase::Decimal, negative: false}));
         assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 15},
-            end: Position{line: 1, column: 18}, source: "9091".to_string(), value: 9091, base: IntegerBase::Decimal}));
+            end: Position{line: 1, column: 18}, source: "9091".to_string(), digits: "9091".to_string(), value: 9091, base: IntegerBase::Decimal, negative: false}));
     }
 
 
     #[test]
     fn test_integer_bin() {
-        let txt = concat!("0b11'00 0B1111 0b1100'0011");
+        let txt = "0b11'00 0B1111 0b1100'0011";
         let mut lxr = Lexer::create(txt.to_string().into_bytes());
 
         assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 1},
-            end: Position{line: 1, column: 7}, source: "0b11'00".to_string(), value: 12, base: IntegerBase::Binary}));
+            end: Position{line: 1, column: 7}, source: "0b11'00".to_string(), digits: "0b1100".to_string(), value: 12, base: IntegerBase::Binary, negative: false}));
         assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 9},
-            end: Position{line: 1, column: 14}, source: "0B1111".to_string(), value: 15, base: IntegerBase::Binary}));
+            end: Position{line: 1, column: 14}, source: "0B1111".to_string(), digits: "0B1111".to_string(), value: 15, base: IntegerBase::Binary, negative: false}));
         assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 16},
-            end: Position{line: 1, column: 26}, source: "0b1100'0011".to_string(), value: 0xc3, base: IntegerBase::Binary}));
+            end: Position{line: 1, column: 26}, source: "0b1100'0011".to_string(), digits: "0b11000011".to_string(), value: 0xc3, base: IntegerBase::Binary, negative: false}));
+    }
+
+    #[test]
+    fn test_integer_oct() {
+        let txt = "0o17 0O7'55 0o8";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+
+        assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 1},
+            end: Position{line: 1, column: 4}, source: "0o17".to_string(), digits: "0o17".to_string(), value: 0o17, base: IntegerBase::Octal, negative: false}));
+        assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 6},
+            end: Position{line: 1, column: 11}, source: "0O7'55".to_string(), digits: "0O755".to_string(), value: 0o755, base: IntegerBase::Octal, negative: false}));
+        assert_eq!(lxr.get(), Err( LexerError::ExpectedDigit(Position{line: 1, column: 14}) ));
     }
 
     #[test]
     fn test_integer_hex() {
-        let txt = concat!("0x0 0XaF22 0x8000'0001");
+        let txt = "0x0 0XaF22 0x8000'0001";
         let mut lxr = Lexer::create(txt.to_string().into_bytes());
 
         assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 1},
-            end: Position{line: 1, column: 3}, source: "0x0".to_string(), value: 0, base: IntegerBase::Hexadecimal}));
+            end: Position{line: 1, column: 3}, source: "0x0".to_string(), digits: "0x0".to_string(), value: 0, base: IntegerBase::Hexadecimal, negative: false}));
         assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 5},
-            end: Position{line: 1, column: 10}, source: "0XaF22".to_string(), value: 0xaf22, base: IntegerBase::Hexadecimal}));
+            end: Position{line: 1, column: 10}, source: "0XaF22".to_string(), digits: "0XaF22".to_string(), value: 0xaf22, base: IntegerBase::Hexadecimal, negative: false}));
         assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 12},
-            end: Position{line: 1, column: 22}, source: "0x8000'0001".to_string(), value: 0x80000001, base: IntegerBase::Hexadecimal}));
+            end: Position{line: 1, column: 22}, source: "0x8000'0001".to_string(), digits: "0x80000001".to_string(), value: 0x80000001, base: IntegerBase::Hexadecimal, negative: false}));
+    }
+
+    #[test]
+    fn test_strict_numbers() {
+        let txt = "12abc";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 1},
+            end: Position{line: 1, column: 2}, source: "12".to_string(), digits: "12".to_string(), value: 12, base: IntegerBase::Decimal, negative: false}));
+        assert_eq!(lxr.get(), Ok( Token::Identifier {start: Position{line: 1, column: 3},
+            end: Position{line: 1, column: 5}, source: "abc".to_string()}));
+
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        lxr.set_strict_numbers(true);
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 3}, 'a')));
+
+        let mut lxr = Lexer::create("0x1fg 2.5 1e3".to_string().into_bytes());
+        lxr.set_strict_numbers(true);
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 5}, 'g')));
+    }
+
+    #[test]
+    fn test_integer_too_long() {
+        let txt = format!(" {}", "9".repeat(DEFAULT_MAX_INTEGER_DIGITS + 1000));
+        let mut lxr = Lexer::create(txt.into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::IntegerTooLong(Position{line: 1, column: 2})));
+        assert_eq!(lxr.pos(), Position{line: 1, column: DEFAULT_MAX_INTEGER_DIGITS as u32 + 1});
+
+        for (txt, ok) in [("1'234", true), ("12'345", false), ("0xff'ff", true), ("0x1'0000", false),
+                          ("0b1111", true), ("0b1'0000", false)] {
+            let mut lxr = Lexer::create(txt.to_string().into_bytes());
+            lxr.set_max_integer_digits(4);
+            let expected = if ok { Ok(()) } else { Err(LexerError::IntegerTooLong(Position{line: 1, column: 1})) };
+            assert_eq!(lxr.get().map(|_| ()), expected, "{}", txt);
+        }
+    }
+
+    #[test]
+    fn test_number_digits() {
+        let mut lxr = Lexer::create("1'000 0xff'ff 1'000.2'5e3".to_string().into_bytes());
+        for (source, digits) in [("1'000", "1000"), ("0xff'ff", "0xffff"), ("1'000.2'5e3", "1000.25e3")] {
+            match lxr.get() {
+                Ok( Token::Integer{source: s, digits: d, ..} ) | Ok( Token::FloatNumber{source: s, digits: d, ..} ) => {
+                    assert_eq!(s, source);
+                    assert_eq!(d, digits);
+                },
+                r => panic!("unexpected {:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn test_integer_separators() {
+        let mut lxr = Lexer::create("1'2".to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::Integer {start: Position{line: 1, column: 1},
+            end: Position{line: 1, column: 3}, source: "1'2".to_string(), digits: "12".to_string(), value: 12, base: IntegerBase::Decimal, negative: false}));
+
+        // a leading quote starts a char literal, not a number
+        let mut lxr = Lexer::create("'12".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 3}, '2')));
+
+        let mut lxr = Lexer::create("12' ".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 3}, '\'')));
+
+        let mut lxr = Lexer::create("1''2".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 3}, '\'')));
+    }
+
+    #[test]
+    fn test_float_separators() {
+        let mut lxr = Lexer::create("1'0.5'0".to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::FloatNumber {start: Position{line: 1, column: 1},
+            end: Position{line: 1, column: 7}, source: "1'0.5'0".to_string(), digits: "10.50".to_string(), value: 10.5}));
+
+        let mut lxr = Lexer::create("1.'5".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 3}, '\'')));
+
+        let mut lxr = Lexer::create("1.5'".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 4}, '\'')));
+
+        let mut lxr = Lexer::create("1.5'e3".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 4}, '\'')));
+
+        let mut lxr = Lexer::create("1e1'0".to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::FloatNumber {start: Position{line: 1, column: 1},
+            end: Position{line: 1, column: 5}, source: "1e1'0".to_string(), digits: "1e10".to_string(), value: 1e10}));
+
+        let mut lxr = Lexer::create("1e'5".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 3}, '\'')));
+
+        let mut lxr = Lexer::create("1e-5' ".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 5}, '\'')));
+    }
+
+    #[test]
+    fn test_integer_separators_hex_bin() {
+        let mut lxr = Lexer::create("0x'1".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 3}, '\'')));
+
+        let mut lxr = Lexer::create("0xf'".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 4}, '\'')));
+
+        let mut lxr = Lexer::create("0b1''0".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 5}, '\'')));
+
+        let mut lxr = Lexer::create("0b'1".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::Unexpected(Position{line: 1, column: 3}, '\'')));
     }
 
     #[test]
     fn test_string_invalid_no_end() {
         let txt = " \"this is a string without";
         let mut lxr = Lexer::create(txt.to_string().into_bytes());
-        assert_eq!(lxr.get(), Err(LexerError::UnexpectedEndOfFile( Position{ line: 1, column: 26})));
+        assert_eq!(lxr.get(), Err(LexerError::UnterminatedString( Position{ line: 1, column: 2})));
+    }
+
+    #[test]
+    fn test_string_invalid_escaped_end() {
+        let mut lxr = Lexer::create("\"abc".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::UnterminatedString( Position{ line: 1, column: 1})));
+
+        let mut lxr = Lexer::create("x \"abc\\".to_string().into_bytes());
+        assert_eq!(lxr.get().unwrap(), Token::Identifier{ start: Position{ line: 1, column: 1},
+            end: Position{ line: 1, column: 1}, source: "x".to_string() });
+        assert_eq!(lxr.get(), Err(LexerError::UnterminatedString( Position{ line: 1, column: 3})));
+    }
+
+    #[test]
+    fn test_string_crlf() {
+        let txt = "\"ab\r\ncd\" x\r\n\"\r\"";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::String{start: Position{line: 1, column: 1},
+            end: Position{line: 2, column: 3}, source: "ab\ncd".to_string()}));
+        assert_eq!(lxr.get(), Ok( Token::Identifier{start: Position{line: 2, column: 5},
+            end: Position{line: 2, column: 5}, source: "x".to_string()}));
+        assert_eq!(lxr.get(), Ok( Token::String{start: Position{line: 3, column: 1},
+            end: Position{line: 3, column: 3}, source: "\r".to_string()}));
+    }
+
+    #[test]
+    fn test_string_line_continuation() {
+        let txt = "\"abc\\\ndef\" \"a\\\r\nb\" x";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        lxr.set_allow_multiline_strings(false);
+        assert_eq!(lxr.get(), Ok( Token::String{start: Position{line: 1, column: 1},
+            end: Position{line: 2, column: 4}, source: "abcdef".to_string()}));
+        assert_eq!(lxr.get(), Ok( Token::String{start: Position{line: 2, column: 6},
+            end: Position{line: 3, column: 2}, source: "ab".to_string()}));
+        assert_eq!(lxr.get(), Ok( Token::Identifier{start: Position{line: 3, column: 4},
+            end: Position{line: 3, column: 4}, source: "x".to_string()}));
+
+        let mut lxr = Lexer::create("'\\\n'".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err( LexerError::InvalidEscape{start: Position{line: 1, column: 2}, ch: '\n'} ));
+    }
+
+    #[test]
+    fn test_string_multiline() {
+        let txt = "\"ab\r\ncd\" \"ef\"";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        lxr.set_allow_multiline_strings(false);
+        assert_eq!(lxr.get(), Err( LexerError::NewlineInString(Position{line: 1, column: 5}) ));
+
+        let mut lxr = Lexer::create("\"ab\rcd\"".to_string().into_bytes());
+        lxr.set_allow_multiline_strings(false);
+        lxr.set_line_ending_mode(LineEndingMode::LfOnly);
+        assert_eq!(lxr.get(), Ok( Token::String{start: Position{line: 1, column: 1},
+            end: Position{line: 1, column: 7}, source: "ab\rcd".to_string()}));
+
+        let mut lxr = Lexer::create("\"ab\u{2028}cd\"".to_string().into_bytes());
+        lxr.set_allow_multiline_strings(false);
+        assert_eq!(lxr.get(), Err( LexerError::NewlineInString(Position{line: 1, column: 4}) ));
+
+        let mut lxr = Lexer::create("\"ab\\ncd\"".to_string().into_bytes());
+        lxr.set_allow_multiline_strings(false);
+        assert_eq!(lxr.get(), Ok( Token::String{start: Position{line: 1, column: 1},
+            end: Position{line: 1, column: 8}, source: "ab\ncd".to_string()}));
+    }
+
+    #[test]
+    fn test_custom_keywords() {
+        let mut keywords = Lexer::default_keywords();
+        keywords.insert("fun", Token::KwFn);
+        keywords.remove("type");
+        let mut lxr = Lexer::with_keywords("fun type fn".to_string().into_bytes(), keywords);
+
+        assert_eq!(lxr.get(), Ok( Token::KwFn(Position{line: 1, column: 1}) ));
+        assert_eq!(lxr.get(), Ok( Token::Identifier{start: Position{line: 1, column: 5},
+            end: Position{line: 1, column: 8}, source: "type".to_string()}));
+        assert_eq!(lxr.get(), Ok( Token::KwFn(Position{line: 1, column: 10}) ));
+    }
+
+    #[test]
+    fn test_string_concat() {
+        let txt = "\"ab\" \"cd\"\n  \"e\" x \"f\"";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        lxr.set_concat_strings(true);
+        assert_eq!(lxr.get(), Ok( Token::String{ start: Position{ line: 1, column: 1},
+            end: Position{ line: 2, column: 5}, source: "abcde".to_string() }));
+        assert_eq!(lxr.get(), Ok( Token::Identifier{ start: Position{ line: 2, column: 7},
+            end: Position{ line: 2, column: 7}, source: "x".to_string() }));
+        assert_eq!(lxr.get(), Ok( Token::String{ start: Position{ line: 2, column: 9},
+            end: Position{ line: 2, column: 11}, source: "f".to_string() }));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
+
+        let mut lxr = Lexer::create("\"ab\" \"cd".to_string().into_bytes());
+        lxr.set_concat_strings(true);
+        assert_eq!(lxr.get(), Err( LexerError::UnterminatedString(Position{ line: 1, column: 6}) ));
+
+        let mut lxr = Lexer::create("\"ab\" \"cd\"".to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::String{ start: Position{ line: 1, column: 1},
+            end: Position{ line: 1, column: 4}, source: "ab".to_string() }));
     }
 
     #[test]
     fn test_string_invalid_unknown_escape() {
         let txt = " \"an invalid escape \\i\"";
         let mut lxr = Lexer::create(txt.to_string().into_bytes());
-        assert_eq!(lxr.get(), Err(LexerError::Unexpected( Position{ line: 1, column: 22}, 'i')));
+        assert_eq!(lxr.get(), Err(LexerError::InvalidEscape{ start: Position{ line: 1, column: 21}, ch: 'i' }));
+
+        let mut lxr = Lexer::create("\"\\q\"".to_string().into_bytes());
+        assert_eq!(lxr.get(), Err(LexerError::InvalidEscape{ start: Position{ line: 1, column: 2}, ch: 'q' }));
     }
 
     #[test]
@@ -751,7 +1602,7 @@ mod test {
     fn test_string_invalid_unknown_unicode_escape_3() {
         let txt = "\"an invalid unicode \\u";
         let mut lxr = Lexer::create(txt.to_string().into_bytes());
-        assert_eq!(lxr.get(), Err(LexerError::UnexpectedEndOfFile( Position{ line: 1, column: 22} )));
+        assert_eq!(lxr.get(), Err(LexerError::UnterminatedString( Position{ line: 1, column: 1} )));
     }
 
     #[test]
@@ -829,14 +1680,31 @@ mod test {
         assert_eq!(lxr.get(), Ok( Token::Comment {start: Position{ line: 2, column: 1},
             comment: "full line comment".to_string()}));
         assert_eq!(lxr.get(), Ok( Token::ExclamationMark( Position{ line: 3, column: 1})));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let txt = concat!(
+            "/// documentation\n",
+            "//// not documentation\n",
+            "///"
+        );
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::DocComment {start: Position{ line: 1, column: 1},
+            comment: " documentation".to_string()}));
+        assert_eq!(lxr.get(), Ok( Token::Comment {start: Position{ line: 2, column: 1},
+            comment: "// not documentation".to_string()}));
+        assert_eq!(lxr.get(), Ok( Token::DocComment {start: Position{ line: 3, column: 1},
+            comment: "".to_string()}));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
     fn test_keywords() {
         let txt = concat!("import i8 i16 i32 i64 u8 u16 u32 u64 \n",
             "bool f32 f64 char fn struct enum\n",
-            "type break continue expect let mut true false");
+            "type break continue expect let mut true false as if else while return match loop for in");
         let mut lxr = Lexer::create(txt.to_string().into_bytes());
 
         assert_eq!(lxr.get(), Ok(Token::KwImport( Position{ column: 1, line: 1} )));
@@ -865,6 +1733,15 @@ mod test {
         assert_eq!(lxr.get(), Ok(Token::KwMut( Position{ column: 32, line: 3} )));
         assert_eq!(lxr.get(), Ok(Token::KwTrue( Position{ column: 36, line: 3} )));
         assert_eq!(lxr.get(), Ok(Token::KwFalse( Position{ column: 41, line: 3} )));
+        assert_eq!(lxr.get(), Ok(Token::KwAs( Position{ column: 47, line: 3} )));
+        assert_eq!(lxr.get(), Ok(Token::KwIf( Position{ column: 50, line: 3} )));
+        assert_eq!(lxr.get(), Ok(Token::KwElse( Position{ column: 53, line: 3} )));
+        assert_eq!(lxr.get(), Ok(Token::KwWhile( Position{ column: 58, line: 3} )));
+        assert_eq!(lxr.get(), Ok(Token::KwReturn( Position{ column: 64, line: 3} )));
+        assert_eq!(lxr.get(), Ok(Token::KwMatch( Position{ column: 71, line: 3} )));
+        assert_eq!(lxr.get(), Ok(Token::KwLoop( Position{ column: 77, line: 3} )));
+        assert_eq!(lxr.get(), Ok(Token::KwFor( Position{ column: 82, line: 3} )));
+        assert_eq!(lxr.get(), Ok(Token::KwIn( Position{ column: 86, line: 3} )));
 
     }
 
@@ -888,7 +1765,7 @@ mod test {
 
         assert_eq!(lxr.get(), Ok(Token::Unequal(Position { column: 1, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::ExclamationMark(Position { column: 4, line: 1 })));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -898,7 +1775,7 @@ mod test {
 
         assert_eq!(lxr.get(), Ok(Token::ScopeSep(Position { column: 1, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::Colon(Position { column: 4, line: 1 })));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -910,7 +1787,7 @@ mod test {
         assert_eq!(lxr.get(), Ok(Token::Range(Position { column: 3, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::Dot(Position { column: 5, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::Dot(Position { column: 7, line: 1 })));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -920,7 +1797,7 @@ mod test {
 
         assert_eq!(lxr.get(), Ok(Token::Caret(Position { column: 1, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::EXorAssign(Position { column: 3, line: 1 })));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -931,7 +1808,7 @@ mod test {
         assert_eq!(lxr.get(), Ok(Token::OrAssign(Position { column: 1, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::LogicOr(Position { column: 4, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::Vert(Position { column: 8, line: 1 })));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -942,7 +1819,7 @@ mod test {
         assert_eq!(lxr.get(), Ok(Token::LogicAnd(Position { column: 1, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::Ampersand(Position { column: 4, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::AndAssign(Position { column: 6, line: 1 })));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -952,7 +1829,7 @@ mod test {
 
         assert_eq!(lxr.get(), Ok(Token::Slash(Position { column: 3, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::DivAssign(Position { column: 5, line: 1 })));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -962,7 +1839,7 @@ mod test {
 
         assert_eq!(lxr.get(), Ok(Token::Star(Position { column: 1, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::MulAssign(Position { column: 3, line: 1 })));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -973,7 +1850,7 @@ mod test {
         assert_eq!(lxr.get(), Ok(Token::SubAssign(Position { column: 2, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::Minus(Position { column: 5, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::RightArrow(Position { column: 7, line: 1 })));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -983,7 +1860,7 @@ mod test {
 
         assert_eq!(lxr.get(), Ok(Token::AddAssign(Position { column: 2, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::Plus(Position { column: 5, line: 1 })));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -994,7 +1871,7 @@ mod test {
         assert_eq!(lxr.get(), Ok(Token::Equals(Position { column: 2, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::Implies(Position { column: 5, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::Assign( Position{ column: 8, line: 1} )));
-        assert_eq!(lxr.get(), Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -1005,7 +1882,7 @@ mod test {
         assert_eq!(lxr.get(), Ok(Token::GreaterThan(Position { column: 2, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::Greater(Position { column: 5, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::ShiftRight( Position{ column: 7, line: 1} )));
-        assert_eq!(lxr.get(),  Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     #[test]
@@ -1016,7 +1893,337 @@ mod test {
         assert_eq!(lxr.get(), Ok(Token::LessThan(Position { column: 4, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::LeftArrow(Position { column: 7, line: 1 })));
         assert_eq!(lxr.get(), Ok(Token::ShiftLeft(Position { column: 10, line: 1 })));
-        assert_eq!(lxr.get(),  Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
+    }
+
+    #[test]
+    fn test_for_each_token() {
+        let txt = "let x = a + 0x12; // comment\n".repeat(500);
+        let lxr = Lexer::create(txt.into_bytes());
+
+        let mut count = 0;
+        let mut comments = 0;
+        assert_eq!(lxr.for_each_token(|token| {
+            count += 1;
+            if let Token::Comment{..} = token {
+                comments += 1;
+            }
+        }), Ok(()));
+        assert_eq!(count, 8 * 500);
+        assert_eq!(comments, 500);
+    }
+
+    #[test]
+    fn test_significant_tokens() {
+        let txt = "/// doc\nlet x = 1; // comment\n// comment\nx";
+        let tokens: Vec<String> = Lexer::create(txt.to_string().into_bytes()).significant_tokens()
+            .map(|token| token.unwrap().spelling().into_owned()).collect();
+        assert_eq!(tokens, vec!["let", "x", "=", "1", ";", "x"]);
+
+        let mut tokens = Lexer::create("a // b\n`c".to_string().into_bytes()).significant_tokens();
+        assert!(tokens.next().unwrap().is_ok());
+        assert_eq!(tokens.next(), Some(Err(LexerError::Unexpected(Position{ line: 2, column: 1}, '`'))));
+        assert_eq!(tokens.next(), None);
+    }
+
+    #[test]
+    fn test_for_each_token_error() {
+        let lxr = Lexer::create("a b ` c".to_string().into_bytes());
+
+        let mut count = 0;
+        assert_eq!(lxr.for_each_token(|_| count += 1),
+                   Err(LexerError::Unexpected(Position{ line: 1, column: 5}, '`')));
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let txt = "café 名前 ifé";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::Identifier{ start: Position{ line: 1, column: 1 },
+            end: Position{ line: 1, column: 4 }, source: "café".to_string() }));
+        assert_eq!(lxr.get(), Ok( Token::Identifier{ start: Position{ line: 1, column: 6 },
+            end: Position{ line: 1, column: 7 }, source: "名前".to_string() }));
+        assert_eq!(lxr.get(), Ok( Token::Identifier{ start: Position{ line: 1, column: 9 },
+            end: Position{ line: 1, column: 11 }, source: "ifé".to_string() }));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
+    }
+
+    #[test]
+    fn test_reset() {
+        let txt = "let x = 0x1F; // done";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        let mut first = vec![];
+        while let Ok(token) = lxr.get() {
+            if let Token::EndOfFile(_) = token {
+                break;
+            }
+            first.push(token);
+        }
+        assert_eq!(first.len(), 6);
+
+        lxr.reset();
+        let mut second = vec![];
+        lxr.for_each_token(|token| second.push(token)).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_byte_position() {
+        let txt = "let größe = 1; // done";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        assert_eq!((lxr.byte_position(), lxr.remaining_bytes()), (0, txt.len()));
+
+        let mut positions = vec![];
+        while !std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )) {
+            positions.push(lxr.byte_position());
+        }
+        assert_eq!(positions, vec![3, 11, 13, 15, 16, txt.len()]);
+        assert_eq!(lxr.remaining_bytes(), 0);
+    }
+
+    #[test]
+    fn test_tokens_in_line() {
+        let txt = "let a = \"x\ny\"; b += 1; // c\n}";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+
+        let tokens = lxr.tokens_in_line(2).unwrap();
+        let kinds: Vec<TokenKind> = tokens.iter().map(|token| token.kind()).collect();
+        assert_eq!(kinds, vec![TokenKind::Semicolon, TokenKind::Identifier, TokenKind::AddAssign,
+            TokenKind::Integer, TokenKind::Semicolon, TokenKind::Comment]);
+        assert_eq!(tokens[0].position(), Position{ line: 2, column: 3 });
+        assert_eq!(lxr.get(), Ok( Token::RightBrace(Position{ line: 3, column: 1 }) ));
+
+        let tokens = lxr.tokens_in_line(1).unwrap();
+        assert_eq!(tokens.len(), 4);
+        assert!(std::matches!(&tokens[3], Token::String{source, ..} if source == "x\ny"));
+        assert_eq!(lxr.tokens_in_line(4), Ok( vec![] ));
+    }
+
+    #[test]
+    fn test_span_text() {
+        let txt = "let größe = 1; // note\n";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        lxr.get().unwrap();
+        let ident = lxr.get().unwrap();
+        assert_eq!(lxr.span_text(ident.position(), ident.end_position()), Some( "größe" ));
+
+        lxr.get().unwrap();
+        lxr.get().unwrap();
+        lxr.get().unwrap();
+        let comment = lxr.get().unwrap();
+        assert_eq!(lxr.span_text(comment.position(), comment.end_position()), Some( "// note" ));
+        assert_eq!(lxr.span_text(Position{ line: 2, column: 1 }, Position{ line: 2, column: 1 }), None);
+    }
+
+    #[test]
+    fn test_underscore_wildcard() {
+        let txt = "_ _x a_b _";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        lxr.set_underscore_wildcard(true);
+        assert_eq!(lxr.get(), Ok( Token::Underscore(Position{ line: 1, column: 1 }) ));
+        assert_eq!(lxr.get(), Ok( Token::Identifier{ start: Position{ line: 1, column: 3 },
+            end: Position{ line: 1, column: 4 }, source: "_x".to_string() }));
+        assert_eq!(lxr.get(), Ok( Token::Identifier{ start: Position{ line: 1, column: 6 },
+            end: Position{ line: 1, column: 8 }, source: "a_b".to_string() }));
+        assert_eq!(lxr.get(), Ok( Token::Underscore(Position{ line: 1, column: 10 }) ));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
+
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::Identifier{ start: Position{ line: 1, column: 1 },
+            end: Position{ line: 1, column: 1 }, source: "_".to_string() }));
+    }
+
+    #[test]
+    fn test_line_directives() {
+        let txt = "a\n#line 100 \"gen.tsl\"\nb\n  c\n#line 7\n#[x]";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        lxr.set_line_directives(true);
+        assert_eq!(lxr.get().unwrap().position(), Position{ line: 1, column: 1 });
+        assert_eq!(lxr.get().unwrap().position(), Position{ line: 100, column: 1 });
+        assert_eq!(lxr.file_name(), Some( "gen.tsl" ));
+        assert_eq!(lxr.get().unwrap().position(), Position{ line: 101, column: 3 });
+        assert_eq!(lxr.get(), Ok( Token::Hash(Position{ line: 7, column: 1 }) ));
+        assert_eq!(lxr.file_name(), Some( "gen.tsl" ));
+        assert_eq!(lxr.get(), Ok( Token::LeftBracket(Position{ line: 7, column: 2 }) ));
+
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        lxr.get().unwrap();
+        assert_eq!(lxr.get(), Ok( Token::Hash(Position{ line: 2, column: 1 }) ));
+        assert_eq!(lxr.file_name(), None);
+
+        let txt = format!("{}x", "#line 1\n".repeat(100_000));
+        let mut lxr = Lexer::create(txt.into_bytes());
+        lxr.set_line_directives(true);
+        assert_eq!(lxr.get().unwrap().position(), Position{ line: 1, column: 1 });
+        assert!(matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
+    }
+
+    #[test]
+    fn test_recover() {
+        let mut data = b"a ".to_vec();
+        data.extend([0xff]);
+        data.extend(b" b ` c");
+        let mut lxr = Lexer::create(data);
+
+        let mut tokens = vec![];
+        let mut errors = vec![];
+        loop {
+            match lxr.get() {
+                Ok(Token::EndOfFile(_)) => break,
+                Ok(token) => tokens.push(token),
+                Err(err) => {
+                    errors.push(err);
+                    lxr.recover();
+                },
+            }
+        }
+        assert_eq!(errors, vec![LexerError::Utf8Error(Position{ line: 1, column: 2 }),
+                                LexerError::Unexpected(Position{ line: 1, column: 7 }, '`')]);
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[1], Token::Identifier{ start: Position{ line: 1, column: 5 },
+            end: Position{ line: 1, column: 5 }, source: "b".to_string() });
+    }
+
+    #[test]
+    fn test_end_of_file_position() {
+        let mut lxr = Lexer::create("a + b\n  c // done".to_string().into_bytes());
+        while !std::matches!(lxr.peek(), Ok( Token::EndOfFile(_) )) {
+            lxr.get().unwrap();
+        }
+        assert_eq!(lxr.get(), Ok( Token::EndOfFile(Position{ line: 2, column: 11 }) ));
+        assert_eq!(lxr.get(), Ok( Token::EndOfFile(lxr.pos()) ));
+    }
+
+    #[test]
+    fn test_integer_above_u64() {
+        let mut lxr = Lexer::create("18446744073709551616 0xffffffffffffffffffffffffffffffff".to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::Integer{ start: Position{ line: 1, column: 1 },
+            end: Position{ line: 1, column: 20 }, source: "18446744073709551616".to_string(),
+            digits: "18446744073709551616".to_string(), value: u64::MAX as u128 + 1, base: IntegerBase::Decimal, negative: false } ));
+        assert!(std::matches!(lxr.get(), Ok( Token::Integer{ value: u128::MAX, .. } )));
+    }
+
+    #[test]
+    fn test_integer_range() {
+        let mut lxr = Lexer::create("0..10 1.5".to_string().into_bytes());
+        assert!(lxr.get().unwrap().eq_ignoring_pos(&Token::Integer{ start: Position::default(),
+            end: Position::default(), source: "0".to_string(), digits: "0".to_string(), value: 0,
+            base: IntegerBase::Decimal, negative: false }));
+        assert_eq!(lxr.get(), Ok( Token::Range(Position{ line: 1, column: 2 }) ));
+        assert_eq!(lxr.get().unwrap().position(), Position{ line: 1, column: 4 });
+        assert_eq!(lxr.get().unwrap().kind(), TokenKind::FloatNumber);
+
+        let mut lxr = Lexer::create("0x0..0xF 0b0..0b1 0o0..0o7".to_string().into_bytes());
+        for _ in 0..3 {
+            assert_eq!(lxr.get().unwrap().kind(), TokenKind::Integer);
+            assert_eq!(lxr.get().unwrap().kind(), TokenKind::Range);
+            assert_eq!(lxr.get().unwrap().kind(), TokenKind::Integer);
+        }
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
+    }
+
+    #[test]
+    fn test_invalid_utf8_in_number() {
+        let mut lxr = Lexer::create(vec![b'0', 0xff]);
+        assert_eq!(lxr.get(), Ok( Token::Integer{ start: Position{ line: 1, column: 1 },
+            end: Position{ line: 1, column: 1 }, source: "0".to_string(), digits: "0".to_string(),
+            value: 0, base: IntegerBase::Decimal, negative: false } ));
+        assert_eq!(lxr.get(), Err( LexerError::Utf8Error(Position{ line: 1, column: 1 }) ));
+
+        for mut data in [b"0x".to_vec(), b"0b".to_vec(), b"1e".to_vec(), b"0x1p".to_vec()] {
+            data.extend([0xc3]);
+            let column = data.len() as u32 - 1;
+            let mut lxr = Lexer::create(data);
+            assert_eq!(lxr.get(), Err( LexerError::Utf8Error(Position{ line: 1, column }) ));
+        }
+    }
+
+    #[test]
+    fn test_tokenize_lossy() {
+        let mut data = b"a `".to_vec();
+        data.extend([0xff]);
+        data.extend(b" b");
+        let tokens = Lexer::create(data).tokenize_lossy();
+        assert_eq!(tokens, vec![
+            Token::Identifier{ start: Position{ line: 1, column: 1 }, end: Position{ line: 1, column: 1 },
+                source: "a".to_string() },
+            Token::Error{ pos: Position{ line: 1, column: 3 }, message: "Unexpected character '`'.".to_string() },
+            Token::Error{ pos: Position{ line: 1, column: 3 }, message: "Invalid UTF-8 encoding.".to_string() },
+            Token::Identifier{ start: Position{ line: 1, column: 6 }, end: Position{ line: 1, column: 6 },
+                source: "b".to_string() },
+        ]);
+        assert_eq!(tokens[1].kind(), TokenKind::Error);
+    }
+
+    #[test]
+    fn test_error_position() {
+        assert_eq!(LexerError::Unexpected(Position{ line: 2, column: 5 }, '`').position(),
+                   Some(Position{ line: 2, column: 5 }));
+        assert_eq!(LexerError::Unspecified.position(), None);
+    }
+
+    #[test]
+    fn test_error_message() {
+        assert_eq!(LexerError::Unexpected(Position{ line: 2, column: 5 }, '`').message(), "Unexpected character '`'.");
+        assert_eq!(LexerError::InvalidEscape{ start: Position{ line: 1, column: 2 }, ch: 'q' }.message(),
+                   "Invalid escape sequence '\\q'.");
+        assert_eq!(LexerError::Unspecified.message(), "Unspecified lexical error.");
+    }
+
+    #[test]
+    fn test_hex_float() {
+        let txt = "0x1p4 0x1.8p1 0X1.8P-1 0xA.8 0x1p";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::FloatNumber{ start: Position{ line: 1, column: 1 },
+            end: Position{ line: 1, column: 5 }, source: "0x1p4".to_string(), digits: "0x1p4".to_string(), value: 16.0 }));
+        assert_eq!(lxr.get(), Ok( Token::FloatNumber{ start: Position{ line: 1, column: 7 },
+            end: Position{ line: 1, column: 13 }, source: "0x1.8p1".to_string(), digits: "0x1.8p1".to_string(), value: 3.0 }));
+        assert_eq!(lxr.get(), Ok( Token::FloatNumber{ start: Position{ line: 1, column: 15 },
+            end: Position{ line: 1, column: 22 }, source: "0X1.8P-1".to_string(), digits: "0X1.8P-1".to_string(), value: 0.75 }));
+        assert_eq!(lxr.get(), Ok( Token::FloatNumber{ start: Position{ line: 1, column: 24 },
+            end: Position{ line: 1, column: 28 }, source: "0xA.8".to_string(), digits: "0xA.8".to_string(), value: 10.5 }));
+        assert_eq!(lxr.get(), Err( LexerError::ExpectedDigit(Position{ line: 1, column: 33 })));
+
+        let mut lxr = Lexer::create("0xF.p1 0xF. 0x0..0x10".to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::FloatNumber{ start: Position{ line: 1, column: 1 },
+            end: Position{ line: 1, column: 6 }, source: "0xF.p1".to_string(), digits: "0xF.p1".to_string(), value: 30.0 }));
+        assert_eq!(lxr.get(), Ok( Token::Integer{ start: Position{ line: 1, column: 8 }, end: Position{ line: 1, column: 10 },
+            source: "0xF".to_string(), digits: "0xF".to_string(), value: 15, base: IntegerBase::Hexadecimal, negative: false }));
+        assert_eq!(lxr.get(), Ok( Token::Dot(Position{ line: 1, column: 11 }) ));
+        assert_eq!(lxr.get(), Ok( Token::Integer{ start: Position{ line: 1, column: 13 }, end: Position{ line: 1, column: 15 },
+            source: "0x0".to_string(), digits: "0x0".to_string(), value: 0, base: IntegerBase::Hexadecimal, negative: false }));
+        assert_eq!(lxr.get(), Ok( Token::Range(Position{ line: 1, column: 16 }) ));
+        assert_eq!(lxr.get(), Ok( Token::Integer{ start: Position{ line: 1, column: 18 }, end: Position{ line: 1, column: 21 },
+            source: "0x10".to_string(), digits: "0x10".to_string(), value: 16, base: IntegerBase::Hexadecimal, negative: false }));
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let txt = "a\r\nb\u{000B}c\u{000C}d";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok( Token::Identifier{ start: Position{ line: 1, column: 1 },
+            end: Position{ line: 1, column: 1 }, source: "a".to_string() }));
+        assert_eq!(lxr.get(), Ok( Token::Identifier{ start: Position{ line: 2, column: 1 },
+            end: Position{ line: 2, column: 1 }, source: "b".to_string() }));
+        assert_eq!(lxr.get(), Ok( Token::Identifier{ start: Position{ line: 2, column: 3 },
+            end: Position{ line: 2, column: 3 }, source: "c".to_string() }));
+        assert_eq!(lxr.get(), Ok( Token::Identifier{ start: Position{ line: 2, column: 5 },
+            end: Position{ line: 2, column: 5 }, source: "d".to_string() }));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
+    }
+
+    #[test]
+    fn test_shift_assign() {
+        let txt = "<< <<= >> >>= <<<=";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        assert_eq!(lxr.get(), Ok(Token::ShiftLeft(Position { column: 1, line: 1 })));
+        assert_eq!(lxr.get(), Ok(Token::ShlAssign(Position { column: 4, line: 1 })));
+        assert_eq!(lxr.get(), Ok(Token::ShiftRight(Position { column: 8, line: 1 })));
+        assert_eq!(lxr.get(), Ok(Token::ShrAssign(Position { column: 11, line: 1 })));
+        assert_eq!(lxr.get(), Ok(Token::ShiftLeft(Position { column: 15, line: 1 })));
+        assert_eq!(lxr.get(), Ok(Token::LessThan(Position { column: 17, line: 1 })));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 
     # [test]
@@ -1038,6 +2245,30 @@ mod test {
         assert_eq!(lxr.get(),  Ok( Token::Comma( Position{ column: 4, line: 4 } )));
         assert_eq!(lxr.get(),  Ok( Token::Semicolon( Position{ column: 5, line: 4 } )));
         assert_eq!(lxr.get(),  Ok( Token::Hash( Position{ column: 6, line: 4 } )));
-        assert_eq!(lxr.get(),  Ok( Token::EndOfFile));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
+    }
+
+    #[test]
+    fn test_question() {
+        let mut lxr = Lexer::create("x?? ?".to_string().into_bytes());
+        let _ = lxr.get();
+        assert_eq!(lxr.get(),  Ok( Token::Question( Position{ column: 2, line: 1 } )));
+        assert_eq!(lxr.get(),  Ok( Token::Question( Position{ column: 3, line: 1 } )));
+        assert_eq!(lxr.get(),  Ok( Token::Question( Position{ column: 5, line: 1 } )));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
+    }
+
+    #[test]
+    fn test_at_dollar() {
+        let txt = "x@$ $name";
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        assert_eq!(lxr.get(),  Ok( Token::Identifier{ start: Position{ column: 1, line: 1 },
+            end: Position{ column: 1, line: 1 }, source: "x".to_string() }));
+        assert_eq!(lxr.get(),  Ok( Token::At( Position{ column: 2, line: 1 } )));
+        assert_eq!(lxr.get(),  Ok( Token::Dollar( Position{ column: 3, line: 1 } )));
+        assert_eq!(lxr.get(),  Ok( Token::Dollar( Position{ column: 5, line: 1 } )));
+        assert_eq!(lxr.get(),  Ok( Token::Identifier{ start: Position{ column: 6, line: 1 },
+            end: Position{ column: 9, line: 1 }, source: "name".to_string() }));
+        assert!(std::matches!(lxr.get(), Ok( Token::EndOfFile(_) )));
     }
 }
@@ -8,12 +8,16 @@ extern crate core;
 
 mod tokens;
 mod lexer;
+mod token_stream;
 mod ast;
 mod parser;
+mod eval;
 
 pub mod util;
 
 pub use lexer::LexerError;
-pub use lexer::Lexer;
-pub use parser::Parser;
+pub use lexer::{Lexer, Keywords};
+pub use token_stream::{TokenStream, TokenSource, TokenBuffer};
+pub use parser::{Parser, ParseError};
 pub use ast::*;
+pub use eval::{Value, EvalError, evaluate};
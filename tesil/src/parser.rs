@@ -3,19 +3,81 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
-use super::tokens::Token;
+use super::tokens::{Token, Associativity};
 use super::lexer::Lexer;
+use super::token_stream::{TokenBuffer, TokenStream};
 use crate::Expression;
 use super::ast;
+use util::utf8::Position;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ParseError {
-    MissingToken(String)
+    MissingToken{ message: String, pos: Position },    // pos is where the token was expected or of the unclosed delimiter
+    ChainedComparison{ message: String, pos: Position },
+    TrailingTokens{ message: String, pos: Position },
+    KeywordAsIdentifier{ message: String, keyword: String, pos: Position },  // pos of the keyword
+    InvalidAssignTarget{ message: String, pos: Position },  // pos of the assignment operator
+    NestingTooDeep{ message: String, pos: Position },
+    BreakValueOutsideLoop{ message: String, pos: Position },    // pos of the 'break'
+    DuplicateField{ message: String, name: String, first: Position, second: Position },
 }
 
+impl ParseError {
+
+    /// Returns the human readable description of the error.
+    pub fn message(&self) -> &str {
+        match self {
+            ParseError::MissingToken{message, ..}
+            | ParseError::ChainedComparison{message, ..}
+            | ParseError::TrailingTokens{message, ..}
+            | ParseError::KeywordAsIdentifier{message, ..}
+            | ParseError::InvalidAssignTarget{message, ..}
+            | ParseError::NestingTooDeep{message, ..}
+            | ParseError::BreakValueOutsideLoop{message, ..}
+            | ParseError::DuplicateField{message, ..} => message,
+        }
+    }
+
+    /// Returns the position in the source code the error refers to.
+    pub fn position(&self) -> Position {
+        match self {
+            ParseError::MissingToken{pos, ..}
+            | ParseError::ChainedComparison{pos, ..}
+            | ParseError::TrailingTokens{pos, ..}
+            | ParseError::KeywordAsIdentifier{pos, ..}
+            | ParseError::InvalidAssignTarget{pos, ..}
+            | ParseError::NestingTooDeep{pos, ..}
+            | ParseError::BreakValueOutsideLoop{pos, ..} => *pos,
+            ParseError::DuplicateField{second, ..} => *second,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Default maximum nesting depth of expressions, see `Parser::set_max_nesting_depth`.
+/// A level takes up to about 18 KiB of stack in unoptimized builds, so that 64 levels stay well
+/// within the 2 MiB stack of a spawned thread, whereas 256 would not.
+pub const DEFAULT_MAX_NESTING_DEPTH: u32 = 64;
+
 /// Parser for TESIL language files producing the corresponding TESIL AST.
 pub struct Parser {
-    lexer: Lexer,
+    tokens: TokenStream,
+    loop_depth: u32,
+    break_values: bool,
+    nesting_depth: u32,
+    max_nesting_depth: u32,
+    reject_chained_comparisons: bool,
+    fold_unary_minus_literals: bool,
+    statement_start: bool,  // the next primary starts an expression statement, see `postfix`
 }
 
 /// Checks whether next token matches one of the given patterns and returns it as 'Some(token)'
@@ -23,75 +85,313 @@ pub struct Parser {
 /// Usage: use inside of lexer method as: `matches(self, Token::LeftParen(_), Token::RightParen(_))`
 macro_rules! matches {
     ($self:ident, $($pats:pat),*) => {
-        match $self.lexer.peek() {
-            $(Ok($pats) => Some($self.lexer.get()),)*
-            _ => None
+        $self.tokens.expect(|token| std::matches!(token, $($pats)|*))
+    }
+}
+
+/// Checks whether next token matches one of the given patterns without consuming it.
+macro_rules! matches_peek {
+    ($self:ident, $($pats:pat),*) => {
+        match $self.tokens.peek() {
+            $(Ok($pats) => true,)*
+            _ => false
         }
     }
 }
 
+/// Consumes and returns the next token if it matches the pattern, otherwise returns a missing
+/// token error with the given message.
 macro_rules! check_token {
     ($self:ident, $pat:pat, $msg:expr) => {
-        match $self.lexer.peek() {
-            Ok($pat) => {
-                let _ = $self.lexer.get();
-                Ok(())
-            },
-            _ => {
-                Err(ParseError::MissingToken($msg))
-            }
+        match $self.tokens.expect(|token| std::matches!(token, $pat)) {
+            Some(token) => Ok(token),
+            None => Err($self.missing_token($msg)),
         }
-    }
+    };
+    // reports the error at `$pos`, e.g. the opening delimiter of a missing closing one
+    ($self:ident, $pat:pat, $msg:expr, $pos:expr) => {
+        match $self.tokens.expect(|token| std::matches!(token, $pat)) {
+            Some(token) => Ok(token),
+            None => Err(ParseError::MissingToken{ message: $msg, pos: $pos }),
+        }
+    };
 }
 
 impl Parser {
 
     pub fn create(data: Vec<u8>) -> Parser {
-        let lexer = Lexer::create( data );
-        Parser{ lexer }
+        let tokens = TokenStream::new( Lexer::create( data ) );
+        Parser{ tokens, loop_depth: 0, break_values: false, nesting_depth: 0, max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            reject_chained_comparisons: false,
+            fold_unary_minus_literals: false, statement_start: false }
+    }
+
+    /// Creates a parser reading already scanned tokens instead of lexing a source, e.g. the tokens
+    /// of `Lexer::tokenize_lossy`. The end of file is implied after the last token.
+    pub fn new_from_tokens(tokens: Vec<Token>) -> Parser {
+        let tokens = TokenStream::with_source(Box::new(TokenBuffer::from(tokens)));
+        Parser{ tokens, loop_depth: 0, break_values: false, nesting_depth: 0, max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            reject_chained_comparisons: false,
+            fold_unary_minus_literals: false, statement_start: false }
     }
     //
     // fn eof(&mut self) -> bool {
-    //     self.lexer.peek() == Ok( Token::EndOfFile )
+    //     self.tokens.peek() == Ok( Token::EndOfFile )
     // }
 
+    /// Enables collecting the comments preceding an item into the item's `doc` field.
+    /// Comments are ignored by default.
+    pub fn set_collect_comments(&mut self, collect: bool) {
+        self.tokens.set_collect_comments(collect);
+    }
+
+    /// Enables rejecting chained comparisons like `a < b < c` or `a < b == c` which are usually
+    /// a bug, the comparisons have to be parenthesized then. Chained comparisons are accepted and
+    /// evaluated left to right by default.
+    pub fn set_reject_chained_comparisons(&mut self, reject: bool) {
+        self.reject_chained_comparisons = reject;
+    }
+
+    /// Enables folding a unary minus applied to a numeric literal into a single negative literal,
+    /// e.g. `-42` is parsed as the literal `-42` instead of `Unary(Minus, 42)`.
+    /// # Notes
+    /// A folded integer literal keeps its magnitude as value and is marked as `negative`. Literals
+    /// whose magnitude exceeds `2^127`, i.e. that are below `i128::MIN`, are not folded.
+    pub fn set_fold_unary_minus_literals(&mut self, fold: bool) {
+        self.fold_unary_minus_literals = fold;
+    }
+
+    /// Sets the maximum depth of nested expressions, e.g. parentheses, blocks, prefix operators,
+    /// right operands of assignments or `else if` chains.
+    /// Deeper nesting is reported as `ParseError::NestingTooDeep` instead of overflowing the stack.
+    /// Defaults to `DEFAULT_MAX_NESTING_DEPTH`.
+    pub fn set_max_nesting_depth(&mut self, max: u32) {
+        self.max_nesting_depth = max;
+    }
+
+    pub fn item(&mut self) -> Result<ast::Item, ParseError> {
+        let next = self.tokens.peek();
+        let doc = self.tokens.take_comments().into_iter()
+            .filter_map(|token| match token {
+                Token::Comment{comment, ..} | Token::DocComment{comment, ..} => Some(comment),
+                _ => None,
+            })
+            .collect();
+        let mut attributes = vec![];
+        if let Ok(Token::Hash(_)) = next {
+            while matches_peek!(self, Token::Hash(_)) {
+                attributes.push(self.attribute()?);
+            }
+        }
+        let kind = match self.tokens.peek() {
+            Ok(Token::KwStruct(_)) => self.struct_item()?,
+            Ok(Token::KwFn(_)) => self.function_item()?,
+            _ => return Err(self.missing_token(format!("Expected item ({}).", self.tokens.next_pos()))),
+        };
+        // comments inside of the item do not document the following one
+        let _ = self.tokens.take_comments();
+        Ok( ast::Item { doc, attributes, kind } )
+    }
+
+    fn attribute(&mut self) -> Result<ast::Attribute, ParseError> {
+        check_token!(self, Token::Hash(_), format!("Expected '#' ({}).", self.tokens.next_pos()))?;
+        check_token!(self, Token::LeftBracket(_),
+            format!("Expected '[' after '#' ({}).", self.tokens.next_pos()))?;
+        let mut path = vec![];
+        loop {
+            path.push(self.identifier("attribute name")?.spelling().into_owned());
+            if matches!(self, Token::ScopeSep(_)).is_none() {
+                break;
+            }
+        }
+        let mut args = vec![];
+        if matches!(self, Token::LeftParen(_)).is_some() {
+            // arguments are kept as plain tokens, nested parentheses must be balanced
+            let mut depth = 0;
+            loop {
+                match self.tokens.next() {
+                    Ok(Token::RightParen(_)) if depth == 0 => break,
+                    Ok(Token::EndOfFile(_)) | Err(_) => return Err(self.missing_token(
+                        format!("Unterminated attribute arguments ({}).", self.tokens.next_pos()))),
+                    Ok(tk) => {
+                        match tk {
+                            Token::LeftParen(_) => depth += 1,
+                            Token::RightParen(_) => depth -= 1,
+                            _ => {},
+                        }
+                        args.push(tk);
+                    },
+                }
+            }
+        }
+        check_token!(self, Token::RightBracket(_),
+            format!("Unterminated attribute, expected ']' ({}).", self.tokens.next_pos()))?;
+        Ok( ast::Attribute { path, args } )
+    }
+
+    fn function_item(&mut self) -> Result<ast::ItemKind, ParseError> {
+        check_token!(self, Token::KwFn(_), format!("Expected 'fn' ({}).", self.tokens.next_pos()))?;
+        let name = self.identifier("function name")?;
+        check_token!(self, Token::LeftParen(_),
+            format!("Expected '(' after function name ({}).", self.tokens.next_pos()))?;
+        let mut params = vec![];
+        while matches!(self, Token::RightParen(_)).is_none() {
+            let name = self.identifier("parameter name")?;
+            check_token!(self, Token::Colon(_),
+                format!("Expected ':' after parameter name ({}).", self.tokens.next_pos()))?;
+            let type_ref = self.type_ref()?;
+            params.push(ast::Field { name, type_ref });
+            if matches!(self, Token::Comma(_)).is_none() {
+                check_token!(self, Token::RightParen(_),
+                    format!("Missing closing parenthesis of parameters ({}).", self.tokens.next_pos()))?;
+                break;
+            }
+        }
+        let return_type = match matches!(self, Token::RightArrow(_)) {
+            Some(_) => Some(self.type_ref()?),
+            None => None,
+        };
+        let body = self.block()?;
+        Ok( ast::ItemKind::Function { name, params, return_type, body } )
+    }
+
+    fn struct_item(&mut self) -> Result<ast::ItemKind, ParseError> {
+        check_token!(self, Token::KwStruct(_), format!("Expected 'struct' ({}).", self.tokens.next_pos()))?;
+        let name = self.identifier("struct name")?;
+        check_token!(self, Token::LeftBrace(_),
+            format!("Expected '{{' after struct name ({}).", self.tokens.next_pos()))?;
+        let mut fields: Vec<ast::Field> = vec![];
+        while matches!(self, Token::RightBrace(_)).is_none() {
+            let name = self.identifier("field name")?;
+            check_token!(self, Token::Colon(_),
+                format!("Expected ':' after field name ({}).", self.tokens.next_pos()))?;
+            let type_ref = self.type_ref()?;
+            if let Some(first) = fields.iter().find(|field| field.name.spelling() == name.spelling()) {
+                let first = first.name.position();
+                let second = name.position();
+                return Err(ParseError::DuplicateField{
+                    message: format!("Duplicate field '{}' ({}), first declared at ({}).", name.spelling(), second, first),
+                    name: name.spelling().to_string(), first, second });
+            }
+            fields.push(ast::Field { name, type_ref });
+            if matches!(self, Token::Comma(_)).is_none() {
+                check_token!(self, Token::RightBrace(_),
+                    format!("Missing closing brace of struct ({}).", self.tokens.next_pos()))?;
+                break;
+            }
+        }
+        Ok( ast::ItemKind::Struct { name, fields } )
+    }
+
+    /// Parses an expression that has to span the whole input, i.e. it must be followed by the end
+    /// of file.
+    pub fn parse_expression_complete(&mut self) -> Result<ast::Expression, ParseError> {
+        let expr = self.expression()?;
+        match self.tokens.peek() {
+            Ok(Token::EndOfFile(_)) => Ok(expr),
+            Ok(token) => {
+                let pos = token.position();
+                Err(ParseError::TrailingTokens{
+                    message: format!("Unexpected '{}' after expression ({}).", token.spelling(), pos), pos })
+            },
+            Err(_) => {
+                let pos = self.tokens.current_pos();
+                Err(ParseError::TrailingTokens{
+                    message: format!("Unexpected input after expression ({}).", pos), pos })
+            },
+        }
+    }
+
+    /// Parses a sequence of statements up to the end of file or a closing brace, which is not
+    /// consumed. Expression statements have to be terminated by a semicolon unless they are
+    /// block-like.
+    pub fn parse_statements(&mut self) -> Result<Vec<ast::Statement>, ParseError> {
+        let mut statements = vec![];
+        while !matches_peek!(self, Token::EndOfFile(_), Token::RightBrace(_)) {
+            statements.push(self.statement()?);
+        }
+        Ok(statements)
+    }
+
     pub fn expression(&mut self) -> Result<ast::Expression, ParseError> {
-        self.equality()
+        self.parse_binary(0)
     }
 
-    fn equality(&mut self) -> Result<ast::Expression, ParseError> {
-        let mut expr = self.comparison()?;
-        while let Some(tk) = matches!(self, Token::Equals(_), Token::Unequal(_)) {
-            expr = ast::Expression::Binary {lhs: Box::new(expr), operator: tk.unwrap(),
-                rhs: Box::new( self.comparison()?) }
+    /// Parses a chain of binary operators of at least precedence `min_prec` by precedence
+    /// climbing, the precedence and associativity are given by `Token::binary_precedence`.
+    fn parse_binary(&mut self, min_prec: u8) -> Result<ast::Expression, ParseError> {
+        self.parse_binary_chain(min_prec, false)
+    }
+
+    /// Parses a chain of binary operators like `parse_binary`, `after_comparison` tells whether the
+    /// first operand is the right operand of a comparison, e.g. `b` in `a == b < c`.
+    fn parse_binary_chain(&mut self, min_prec: u8, mut after_comparison: bool) -> Result<ast::Expression, ParseError> {
+        let mut expr = self.cast()?;
+        while let Some(tk) = self.tokens.expect(|token|
+                token.binary_precedence().is_some_and(|(prec, _)| prec >= min_prec)) {
+            let (prec, associativity) = tk.binary_precedence().expect("binary operator");
+            let rhs_prec = match associativity {
+                Associativity::Left => prec + 1,
+                Associativity::Right => prec,
+            };
+            if Parser::is_assignment(&tk) {
+                Parser::check_assign_target(&expr, &tk)?;
+                // assignments are right associative: a = b = c is a = (b = c)
+                expr = ast::Expression::Assign {target: Box::new(expr), operator: tk,
+                    value: Box::new(self.nested(|prs| prs.parse_binary(rhs_prec))?) };
+                continue;
+            }
+            let comparison = Parser::is_comparison(&tk);
+            if comparison {
+                // comparisons of any level must not follow each other without parentheses
+                self.check_chained_comparison(after_comparison)?;
+                after_comparison = true;
+            }
+            expr = ast::Expression::Binary {lhs: Box::new(expr), operator: tk,
+                rhs: Box::new(self.nested(|prs| prs.parse_binary_chain(rhs_prec, comparison))?) }
         }
         Ok(expr)
     }
 
-    fn comparison(&mut self) -> Result<ast::Expression, ParseError> {
-        let mut expr = self.term()?;
-        while let Some(tk) = matches!(self, Token::Greater(_), Token::GreaterThan(_),
-                Token::Less(_), Token::LessThan(_)) {
-            expr = ast::Expression::Binary {lhs: Box::new(expr), operator: tk.unwrap(),
-                rhs: Box::new( self.term()?) }
+    fn is_assignment(tk: &Token) -> bool {
+        std::matches!(tk, Token::Assign(_) | Token::AddAssign(_) | Token::SubAssign(_)
+            | Token::MulAssign(_) | Token::DivAssign(_) | Token::AndAssign(_) | Token::OrAssign(_)
+            | Token::EXorAssign(_) | Token::ShlAssign(_) | Token::ShrAssign(_))
+    }
+
+    fn is_comparison(tk: &Token) -> bool {
+        std::matches!(tk, Token::Equals(_) | Token::Unequal(_) | Token::Greater(_)
+            | Token::GreaterThan(_) | Token::Less(_) | Token::LessThan(_))
+    }
+
+    fn check_assign_target(target: &ast::Expression, tk: &Token) -> Result<(), ParseError> {
+        if !std::matches!(target, Expression::Identifier(_) | Expression::Member{..}
+                | Expression::Index{..} | Expression::Deref{..}) {
+            let pos = tk.position();
+            return Err(ParseError::InvalidAssignTarget{
+                message: format!("Invalid left-hand side of '{}' ({}).", tk.spelling(), pos), pos })
         }
-        Ok(expr)
+        Ok(())
+    }
+
+    /// Creates a missing token error at the start of the peeked token.
+    fn missing_token(&self, message: String) -> ParseError {
+        ParseError::MissingToken{ message, pos: self.tokens.next_pos() }
     }
 
-    fn term(&mut self) -> Result<ast::Expression, ParseError> {
-        let mut expr = self.factor()?;
-        while let Some(tk) = matches!(self, Token::Minus(_), Token::Plus(_) ) {
-            expr = ast::Expression::Binary {lhs: Box::new(expr), operator: tk.unwrap(),
-                                            rhs: Box::new( self.factor()?) }
+    fn check_chained_comparison(&self, chained: bool) -> Result<(), ParseError> {
+        if chained && self.reject_chained_comparisons {
+            let pos = self.tokens.next_pos();
+            return Err(ParseError::ChainedComparison{
+                message: format!("Chained comparison requires parentheses ({}).", pos), pos })
         }
-        Ok(expr)
+        Ok(())
     }
 
-    fn factor(&mut self) -> Result<ast::Expression, ParseError> {
+    fn cast(&mut self) -> Result<ast::Expression, ParseError> {
         let mut expr = self.unary()?;
-        while let Some(tk) = matches!(self, Token::Star(_), Token::Slash(_)) {
-            expr = ast::Expression::Binary {lhs: Box::new(expr), operator: tk.unwrap(),
-                                            rhs: Box::new(self.unary()?) }
+        while matches!(self, Token::KwAs(_)).is_some() {
+            expr = ast::Expression::Cast {expr: Box::new(expr), target: self.type_ref()? }
         }
         Ok(expr)
     }
@@ -99,37 +399,480 @@ impl Parser {
     fn unary(&mut self) -> Result<ast::Expression, ParseError> {
         if let Some(tk) =
                 matches!(self, Token::Minus(_),Token::ExclamationMark(_), Token::Tilde(_)) {
-            return Ok( ast::Expression::Unary {operator: tk.unwrap(), rhs: Box::new(self.unary()?) } )
+            let rhs = self.nested(Parser::unary)?;
+            if let Token::Minus(pos) = tk {
+                if self.fold_unary_minus_literals {
+                    if let Expression::Literal(literal) = &rhs {
+                        if let Some(folded) = Parser::negate_literal(pos, literal) {
+                            return Ok( Expression::Literal(folded) )
+                        }
+                    }
+                }
+            }
+            return Ok( ast::Expression::Unary {operator: tk, rhs: Box::new(rhs) } )
+        }
+        let ampersand = match self.split_logic_and_into_two_ampersands() {
+            Some(token) => Some(token),
+            None => matches!(self, Token::Ampersand(_)),
+        };
+        if let Some(operator) = ampersand {
+            let mutable = matches!(self, Token::KwMut(_)).is_some();
+            return Ok( ast::Expression::Ref {operator, mutable, expr: Box::new(self.nested(Parser::unary)?) } )
+        }
+        if let Some(operator) = matches!(self, Token::Star(_)) {
+            return Ok( ast::Expression::Deref {operator, expr: Box::new(self.nested(Parser::unary)?) } )
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<ast::Expression, ParseError> {
+        let statement_start = std::mem::take(&mut self.statement_start);
+        let mut expr = self.primary()?;
+        loop {
+            if matches!(self, Token::Question(_)).is_some() {
+                expr = Expression::Try { expr: Box::new(expr) };
+            }
+            else if matches!(self, Token::Dot(_)).is_some() {
+                let name = self.identifier("member name after '.'")?;
+                expr = Expression::Member { object: Box::new(expr), name };
+            }
+            else if statement_start && Parser::is_block_like(&expr) {
+                // a parenthesized expression or bracket after a block statement must not become a
+                // call or an index
+                return Ok(expr)
+            }
+            else if let Some(Token::LeftParen(pos)) = matches!(self, Token::LeftParen(_)) {
+                let mut args = vec![];
+                let close = loop {
+                    if let Some(close) = matches!(self, Token::RightParen(_)) {
+                        break close;
+                    }
+                    args.push(self.expression()?);
+                    if matches!(self, Token::Comma(_)).is_none() {
+                        break check_token!(self, Token::RightParen(_),
+                            format!("Missing closing parentheses for call arguments ({}).", pos), pos)?;
+                    }
+                };
+                expr = Expression::Call { callee: Box::new(expr), args, close: close.position() };
+            }
+            else if let Some(Token::LeftBracket(pos)) = matches!(self, Token::LeftBracket(_)) {
+                let index = self.expression()?;
+                let close = check_token!(self, Token::RightBracket(_),
+                    format!("Missing closing bracket for opening bracket ({}).", pos), pos)?;
+                expr = Expression::Index { object: Box::new(expr), index: Box::new(index), close: close.position() };
+            }
+            else {
+                return Ok(expr)
+            }
+        }
+    }
+
+    /// Returns the negated numeric literal starting at the position of the minus sign, or `None`
+    /// if the literal cannot be negated.
+    fn negate_literal(pos: Position, literal: &Token) -> Option<Token> {
+        match literal {
+            Token::Integer{end, source, digits, value, base, negative: false, ..} if *value <= i128::MIN.unsigned_abs() =>
+                Some( Token::Integer{ start: pos, end: *end, source: format!("-{}", source), digits: format!("-{}", digits),
+                    value: *value, base: *base, negative: true } ),
+            Token::FloatNumber{end, source, digits, value, ..} if value.is_sign_positive() =>
+                Some( Token::FloatNumber{ start: pos, end: *end, source: format!("-{}", source),
+                    digits: format!("-{}", digits), value: -value } ),
+            _ => None,
         }
-        self.primary()
     }
 
     fn primary(&mut self) -> Result<ast::Expression, ParseError> {
+        self.nested(Parser::primary_unchecked)
+    }
+
+    /// Runs `parse` one nesting level deeper, failing if the maximum nesting depth is exceeded.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Parser) -> Result<T, ParseError>) -> Result<T, ParseError> {
+        if self.nesting_depth >= self.max_nesting_depth {
+            let pos = self.tokens.next_pos();
+            return Err(ParseError::NestingTooDeep{
+                message: format!("Expression nested too deeply ({}).", pos), pos })
+        }
+        self.nesting_depth += 1;
+        let result = parse(self);
+        self.nesting_depth -= 1;
+        result
+    }
+
+    fn primary_unchecked(&mut self) -> Result<ast::Expression, ParseError> {
         if let Some(tk) = matches!(self, Token::Integer{..},
                 Token::FloatNumber {..}, Token::String{..}, Token::Char {..}, Token::KwFalse(_),
                 Token::KwTrue(_)) {
-            return Ok( Expression::Literal(tk.unwrap()))
+            return Ok( Expression::Literal(tk))
         }
-        else if let Ok(Token::LeftParen(pos)) = self.lexer.peek() {
+        else if let Some(tk) = matches!(self, Token::Identifier{..}) {
+            return Ok( Expression::Identifier(tk))
+        }
+        else if let Ok(Token::LeftBrace(_)) = self.tokens.peek() {
+            return self.block()
+        }
+        else if let Ok(Token::KwIf(_)) = self.tokens.peek() {
+            return self.if_expression()
+        }
+        else if let Ok(Token::KwMatch(_)) = self.tokens.peek() {
+            return self.match_expression()
+        }
+        else if let Ok(Token::LeftParen(pos)) = self.tokens.peek() {
             self.advance();
+            if let Some(close) = matches!(self, Token::RightParen(_)) {
+                return Ok( Expression::Tuple{ open: pos, elements: vec![], close: close.position() } )
+            }
             let expr = self.expression()?;
-            check_token!(self, Token::RightParen(_),
-                format!("Missing closing parentheses for opening parentheses ({}).", pos))?;
-            return Ok( expr )
+            if matches!(self, Token::Comma(_)).is_none() {
+                // a single expression without a comma is a grouping, not a tuple
+                check_token!(self, Token::RightParen(_),
+                    format!("Missing closing parentheses for opening parentheses ({}).", pos), pos)?;
+                return Ok( expr )
+            }
+            let mut elements = vec![expr];
+            let close = loop {
+                if let Some(close) = matches!(self, Token::RightParen(_)) {
+                    break close;
+                }
+                elements.push(self.expression()?);
+                if matches!(self, Token::Comma(_)).is_none() {
+                    break check_token!(self, Token::RightParen(_),
+                        format!("Missing closing parentheses for opening parentheses ({}).", pos), pos)?;
+                }
+            };
+            return Ok( Expression::Tuple{ open: pos, elements, close: close.position() } )
+        }
+        Err(self.missing_token(format!("Expected literal ({}).", self.tokens.next_pos())))
+    }
+
+    fn block(&mut self) -> Result<ast::Expression, ParseError> {
+        let pos = match self.tokens.peek() {
+            Ok(Token::LeftBrace(pos)) => pos,
+            _ => return Err(self.missing_token(format!("Expected block ({}).", self.tokens.next_pos()))),
+        };
+        self.advance();
+        let mut statements = vec![];
+        loop {
+            match self.tokens.peek() {
+                Ok(Token::RightBrace(close)) => {
+                    self.advance();
+                    return Ok( ast::Expression::Block { open: pos, statements, tail: None, close } )
+                },
+                Ok(Token::EndOfFile(_)) => return Err(ParseError::MissingToken{
+                    message: format!("Missing closing brace for opening brace ({}).", pos), pos }),
+                Ok(Token::KwLet(_)) | Ok(Token::KwWhile(_)) | Ok(Token::KwLoop(_)) | Ok(Token::KwFor(_))
+                    | Ok(Token::KwBreak(_)) | Ok(Token::KwContinue(_)) | Ok(Token::KwReturn(_))
+                    | Ok(Token::KwExpect(_)) =>
+                    statements.push(self.statement()?),
+                _ => {
+                    self.statement_start = true;
+                    let expr = self.expression()?;
+                    if matches!(self, Token::Semicolon(_)).is_some() {
+                        statements.push(ast::Statement::Expr(expr));
+                        continue;
+                    }
+                    if Parser::is_block_like(&expr) && !matches_peek!(self, Token::RightBrace(_)) {
+                        statements.push(ast::Statement::Expr(expr));
+                        continue;
+                    }
+                    let close = check_token!(self, Token::RightBrace(_),
+                        format!("Missing closing brace for opening brace ({}).", pos), pos)?;
+                    return Ok( ast::Expression::Block { open: pos, statements, tail: Some(Box::new(expr)),
+                        close: close.position() } )
+                }
+            }
+        }
+    }
+
+    fn if_expression(&mut self) -> Result<ast::Expression, ParseError> {
+        let keyword = check_token!(self, Token::KwIf(_), format!("Expected 'if' ({}).", self.tokens.next_pos()))?;
+        if let Ok(Token::LeftBrace(pos)) = self.tokens.peek() {
+            return Err(ParseError::MissingToken{ message: format!("Missing condition for 'if' ({}).", pos), pos })
+        }
+        let cond = self.expression()?;
+        let then_block = self.block()?;
+        let else_block = match matches!(self, Token::KwElse(_)) {
+            Some(_) => match self.tokens.peek() {
+                Ok(Token::KwIf(_)) => Some(Box::new(self.nested(Parser::if_expression)?)),
+                _ => Some(Box::new(self.block()?)),
+            },
+            None => None,
+        };
+        Ok( ast::Expression::If { keyword: keyword.position(), cond: Box::new(cond), then_block: Box::new(then_block),
+            else_block } )
+    }
+
+    fn match_expression(&mut self) -> Result<ast::Expression, ParseError> {
+        let keyword = check_token!(self, Token::KwMatch(_), format!("Expected 'match' ({}).", self.tokens.next_pos()))?;
+        if let Ok(Token::LeftBrace(pos)) = self.tokens.peek() {
+            return Err(ParseError::MissingToken{ message: format!("Missing expression for 'match' ({}).", pos), pos })
         }
-        Err(ParseError::MissingToken(format!("Expected literal ({}).", self.lexer.pos())))
+        let scrutinee = self.expression()?;
+        let pos = match self.tokens.peek() {
+            Ok(Token::LeftBrace(pos)) => pos,
+            _ => return Err(self.missing_token(
+                format!("Expected '{{' for match arms ({}).", self.tokens.next_pos()))),
+        };
+        self.advance();
+        let mut arms = vec![];
+        let close = loop {
+            if let Some(close) = matches!(self, Token::RightBrace(_)) {
+                break close;
+            }
+            let pattern = self.pattern()?;
+            check_token!(self, Token::Implies(_),
+                format!("Missing '=>' after match pattern ({}).", self.tokens.next_pos()))?;
+            arms.push((pattern, self.expression()?));
+            if matches!(self, Token::Comma(_)).is_none() {
+                break check_token!(self, Token::RightBrace(_),
+                    format!("Missing closing brace for match arms ({}).", pos), pos)?;
+            }
+        };
+        Ok( ast::Expression::Match { keyword: keyword.position(), scrutinee: Box::new(scrutinee), arms,
+            close: close.position() } )
+    }
+
+    /// Parses the pattern of a match arm, a literal, a binding identifier or the wildcard `_`.
+    fn pattern(&mut self) -> Result<ast::Pattern, ParseError> {
+        if let Some(tk) = matches!(self, Token::Integer{..}, Token::FloatNumber {..}, Token::String{..},
+                Token::Char {..}, Token::KwFalse(_), Token::KwTrue(_)) {
+            return Ok( ast::Pattern::Literal(tk) )
+        }
+        match matches!(self, Token::Underscore(_), Token::Identifier{..}) {
+            Some(Token::Underscore(pos)) => Ok( ast::Pattern::Wildcard(pos) ),
+            // without `Lexer::set_underscore_wildcard` a lone '_' is scanned as identifier
+            Some(Token::Identifier{start, source, ..}) if source == "_" => Ok( ast::Pattern::Wildcard(start) ),
+            Some(tk) => Ok( ast::Pattern::Binding(tk) ),
+            None => Err(self.missing_token(format!("Expected pattern ({}).", self.tokens.next_pos()))),
+        }
+    }
+
+    /// Block-like expressions can be used as statements without a terminating semicolon.
+    fn is_block_like(expr: &ast::Expression) -> bool {
+        std::matches!(expr, ast::Expression::Block{..} | ast::Expression::If{..} | ast::Expression::Match{..})
+    }
+
+    fn statement(&mut self) -> Result<ast::Statement, ParseError> {
+        match self.tokens.peek() {
+            Ok(Token::KwLet(_)) => self.let_statement(),
+            Ok(Token::KwWhile(_)) => self.while_statement(),
+            Ok(Token::KwLoop(_)) => self.loop_statement(),
+            Ok(Token::KwFor(_)) => self.for_statement(),
+            Ok(Token::KwBreak(_)) | Ok(Token::KwContinue(_)) => self.loop_control_statement(),
+            Ok(Token::KwReturn(_)) => self.return_statement(),
+            Ok(Token::KwExpect(_)) => self.expect_statement(),
+            _ => {
+                self.statement_start = true;
+                let expr = self.expression()?;
+                if Parser::is_block_like(&expr) {
+                    let _ = matches!(self, Token::Semicolon(_));
+                    return Ok( ast::Statement::Expr(expr) )
+                }
+                check_token!(self, Token::Semicolon(_),
+                    format!("Missing semicolon after expression statement ({}).", self.tokens.next_pos()))?;
+                Ok( ast::Statement::Expr(expr) )
+            }
+        }
+    }
+
+    fn while_statement(&mut self) -> Result<ast::Statement, ParseError> {
+        check_token!(self, Token::KwWhile(_), format!("Expected 'while' ({}).", self.tokens.next_pos()))?;
+        if let Ok(Token::LeftBrace(pos)) = self.tokens.peek() {
+            return Err(ParseError::MissingToken{ message: format!("Missing condition for 'while' ({}).", pos), pos })
+        }
+        let cond = self.expression()?;
+        let body = self.loop_body(false)?;
+        Ok( ast::Statement::While { cond, body } )
+    }
+
+    fn loop_statement(&mut self) -> Result<ast::Statement, ParseError> {
+        check_token!(self, Token::KwLoop(_), format!("Expected 'loop' ({}).", self.tokens.next_pos()))?;
+        let body = self.loop_body(true)?;
+        Ok( ast::Statement::Loop { body } )
+    }
+
+    fn for_statement(&mut self) -> Result<ast::Statement, ParseError> {
+        check_token!(self, Token::KwFor(_), format!("Expected 'for' ({}).", self.tokens.next_pos()))?;
+        let binding = self.identifier("loop variable after 'for'")?;
+        check_token!(self, Token::KwIn(_),
+            format!("Missing 'in' after loop variable ({}).", self.tokens.next_pos()))?;
+        if let Ok(Token::LeftBrace(pos)) = self.tokens.peek() {
+            return Err(ParseError::MissingToken{ message: format!("Missing iterable for 'for' ({}).", pos), pos })
+        }
+        let iter = self.expression()?;
+        let body = self.loop_body(false)?;
+        Ok( ast::Statement::For { binding, iter, body } )
+    }
+
+    /// Parses the block of a loop, `break_values` tells whether a `break` may yield a value.
+    fn loop_body(&mut self, break_values: bool) -> Result<ast::Expression, ParseError> {
+        let outer_break_values = std::mem::replace(&mut self.break_values, break_values);
+        self.loop_depth += 1;
+        let body = self.nested(Parser::block);
+        self.loop_depth -= 1;
+        self.break_values = outer_break_values;
+        body
+    }
+
+    /// Parses `break` or `continue`. A `break` within `loop` may carry a value, which is kept in
+    /// `Statement::Break` only: `loop` is a statement and does not yield the value.
+    fn loop_control_statement(&mut self) -> Result<ast::Statement, ParseError> {
+        let tk = match self.tokens.next() {
+            Ok(tk @ Token::KwBreak(_)) | Ok(tk @ Token::KwContinue(_)) => tk,
+            _ => return Err(self.missing_token(
+                format!("Expected 'break' or 'continue' ({}).", self.tokens.next_pos()))),
+        };
+        if self.loop_depth == 0 {
+            return Err(self.missing_token(
+                format!("'break' and 'continue' are only allowed inside loops ({}).", self.tokens.next_pos())))
+        }
+        let statement = match tk {
+            Token::KwBreak(pos) if !matches_peek!(self, Token::Semicolon(_)) => {
+                if !self.break_values {
+                    return Err(ParseError::BreakValueOutsideLoop{
+                        message: format!("'break' with a value is only allowed inside 'loop' ({}).", pos), pos })
+                }
+                ast::Statement::Break(tk, Some(self.expression()?))
+            },
+            Token::KwBreak(_) => ast::Statement::Break(tk, None),
+            _ => ast::Statement::Continue(tk),
+        };
+        check_token!(self, Token::Semicolon(_),
+            format!("Missing semicolon after loop control statement ({}).", self.tokens.next_pos()))?;
+        Ok( statement )
+    }
+
+    fn return_statement(&mut self) -> Result<ast::Statement, ParseError> {
+        check_token!(self, Token::KwReturn(_), format!("Expected 'return' ({}).", self.tokens.next_pos()))?;
+        let value = match self.tokens.peek() {
+            Ok(Token::Semicolon(_)) => None,
+            _ => Some(self.expression()?),
+        };
+        check_token!(self, Token::Semicolon(_),
+            format!("Missing semicolon after return statement ({}).", self.tokens.next_pos()))?;
+        Ok( ast::Statement::Return(value) )
+    }
+
+    fn expect_statement(&mut self) -> Result<ast::Statement, ParseError> {
+        check_token!(self, Token::KwExpect(_), format!("Expected 'expect' ({}).", self.tokens.next_pos()))?;
+        let condition = self.expression()?;
+        let implication = match matches!(self, Token::Implies(_)) {
+            Some(_) => Some( self.expression()? ),
+            None => None,
+        };
+        let message = match matches!(self, Token::Colon(_)) {
+            Some(_) => match self.tokens.next() {
+                Ok(Token::String{source, ..}) => Some(source),
+                _ => return Err(self.missing_token(
+                    format!("Expected message string after ':' ({}).", self.tokens.next_pos()))),
+            },
+            None => None,
+        };
+        check_token!(self, Token::Semicolon(_),
+            format!("Missing semicolon after expect statement ({}).", self.tokens.next_pos()))?;
+        Ok( ast::Statement::Expect { condition, implication, message } )
+    }
+
+    fn let_statement(&mut self) -> Result<ast::Statement, ParseError> {
+        check_token!(self, Token::KwLet(_), format!("Expected 'let' ({}).", self.tokens.next_pos()))?;
+        let mutable = matches!(self, Token::KwMut(_)).is_some();
+        let name = self.identifier("identifier")?;
+        let type_ref = match matches!(self, Token::Colon(_)) {
+            Some(_) => Some(self.type_ref()?),
+            None => None,
+        };
+        let value = match matches!(self, Token::Assign(_)) {
+            Some(_) => Some(self.expression()?),
+            None => None,
+        };
+        check_token!(self, Token::Semicolon(_),
+            format!("Missing semicolon after let statement ({}).", self.tokens.next_pos()))?;
+        Ok( ast::Statement::Let { name, mutable, type_ref, value } )
+    }
+
+    fn type_ref(&mut self) -> Result<ast::TypeRef, ParseError> {
+        if let Some(tk) = matches!(self, Token::KwTypeI8(_), Token::KwTypeI16(_),
+                Token::KwTypeI32(_), Token::KwTypeI64(_), Token::KwTypeU8(_), Token::KwTypeU16(_),
+                Token::KwTypeU32(_), Token::KwTypeU64(_), Token::KwTypeBool(_), Token::KwTypeF32(_),
+                Token::KwTypeF64(_), Token::KwTypeChar(_)) {
+            return Ok( ast::TypeRef::Primitive(tk))
+        }
+        else if let Some(tk) = matches!(self, Token::Identifier{..}) {
+            return Ok( ast::TypeRef::Named(tk))
+        }
+        Err(self.missing_token(format!("Expected type ({}).", self.tokens.next_pos())))
+    }
+
+    /// Consumes an identifier token, `what` describes the expected name in the error message.
+    /// A keyword in place of the identifier is reported as `ParseError::KeywordAsIdentifier`.
+    fn identifier(&mut self, what: &str) -> Result<Token, ParseError> {
+        if let Some(tk) = matches!(self, Token::Identifier{..}) {
+            return Ok( tk )
+        }
+        match self.peek_keyword() {
+            Some(keyword) => {
+                let pos = keyword.position();
+                Err(ParseError::KeywordAsIdentifier{
+                    message: format!("Keyword '{}' cannot be used as {} ({}).", keyword.spelling(), what, pos),
+                    keyword: keyword.spelling().into_owned(), pos })
+            },
+            None => Err(self.missing_token(format!("Expected {} ({}).", what, self.tokens.next_pos()))),
+        }
+    }
+
+    /// Returns the next token without consuming it if it is a keyword.
+    fn peek_keyword(&mut self) -> Option<Token> {
+        match self.tokens.peek() {
+            Ok(token) if token.is_keyword() => Some(token),
+            _ => None,
+        }
+    }
+
+    /// Splits a following `>>` into two `>` where closing angle brackets are expected, e.g. for
+    /// `Vec<Map<i32>>`: the first `>` is consumed and returned, the second is pushed back into
+    /// the token stream.
+    /// # Returns
+    /// `None` without consuming anything if the next token is not `>>`.
+    pub fn split_shr_into_two_gt(&mut self) -> Option<Token> {
+        let pos = match self.tokens.peek() {
+            Ok(Token::ShiftRight(pos)) => pos,
+            _ => return None,
+        };
+        self.advance();
+        self.tokens.unget(Token::Greater(pos.advanced(1)));
+        Some( Token::Greater(pos) )
+    }
+
+    /// Splits a following `&&` into two `&` in prefix position, e.g. for the reference to a
+    /// reference `&&x`, the same way as `split_shr_into_two_gt`.
+    fn split_logic_and_into_two_ampersands(&mut self) -> Option<Token> {
+        let pos = match self.tokens.peek() {
+            Ok(Token::LogicAnd(pos)) => pos,
+            _ => return None,
+        };
+        self.advance();
+        self.tokens.unget(Token::Ampersand(pos.advanced(1)));
+        Some( Token::Ampersand(pos) )
     }
 
     fn advance(&mut self) {
-        let _ = self.lexer.get().unwrap();
+        let _ = self.tokens.next();
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ParseError;
+
+    /// Parses a complete expression, e.g. `let e: Expression = "1 + 2".parse()?;`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::create(s.as_bytes().to_vec()).parse_expression_complete()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use util::utf8::Position;
     use super::super::tokens::IntegerBase;
+    use crate::{TypeRef, Statement, Item, ItemKind, Field, Attribute, Pattern};
 
     fn position(line: u32, column: u32) -> Position {
         Position{ line, column }
@@ -140,17 +883,17 @@ mod test {
         let txt = "1*2 \"ajb\"/\"bca\"";
         let mut prs = Parser::create(txt.to_string().into_bytes());
 
-        assert_eq!(prs.factor(), Ok( Expression::Binary {
+        assert_eq!(prs.parse_binary(6), Ok( Expression::Binary {
             lhs: Box::new(Expression::Literal(
                 Token::Integer{start: position(1,1), end: position(1,1),
-                                               source: "1".to_string(), value: 1, base: IntegerBase::Decimal })),
+                                               source: "1".to_string(), digits: "1".to_string(), value: 1, base: IntegerBase::Decimal, negative: false })),
             operator: Token::Star(position(1, 2)),
             rhs: Box::new(Expression::Literal(
                 Token::Integer{start: position(1,3), end: position(1,3),
-                    source: "2".to_string(), value: 2, base: IntegerBase::Decimal })),
+                    source: "2".to_string(), digits: "2".to_string(), value: 2, base: IntegerBase::Decimal, negative: false })),
         }));
 
-        assert_eq!(prs.factor(), Ok( Expression::Binary {
+        assert_eq!(prs.parse_binary(6), Ok( Expression::Binary {
             lhs: Box::new(Expression::Literal(
                 Token::String{start: position(1,5), end: position(1,9), source: "ajb".to_string() })),
             operator: Token::Slash(position(1, 10)),
@@ -161,15 +904,15 @@ mod test {
 
     #[test]
     fn test_expression_unary() {
-        let txt = "1245 (2.3) !false ~22 -42";
+        let txt = "(2.3) 1245 !false ~22 -42";
         let mut prs = Parser::create(txt.to_string().into_bytes());
 
         assert_eq!(prs.unary(), Ok( Expression::Literal(
-            Token::Integer{start: position(1,1), end: position(1, 4),
-                source:"1245".to_string(), value: 1245, base: IntegerBase::Decimal })));
+            Token::FloatNumber{start: position(1, 2), end: position(1,4),
+                source:"2.3".to_string(), digits: "2.3".to_string(), value: 2.3 })));
         assert_eq!(prs.unary(), Ok( Expression::Literal(
-            Token::FloatNumber{start: position(1, 7), end: position(1,9),
-                source:"2.3".to_string(), value: 2.3 })));
+            Token::Integer{start: position(1,7), end: position(1, 10),
+                source:"1245".to_string(), digits: "1245".to_string(), value: 1245, base: IntegerBase::Decimal, negative: false })));
         assert_eq!(prs.unary(), Ok( Expression::Unary {
             operator: Token::ExclamationMark(position(1, 12)),
             rhs: Box::new(Expression::Literal( Token::KwFalse(position(1,13)) ))}));
@@ -177,11 +920,913 @@ mod test {
             operator: Token::Tilde(position(1, 19)),
             rhs: Box::new(Expression::Literal(
                 Token::Integer{start: position(1,20), end: position(1,21),
-                    source:"22".to_string(), value: 22, base: IntegerBase::Decimal}))}));
+                    source:"22".to_string(), digits: "22".to_string(), value: 22, base: IntegerBase::Decimal, negative: false}))}));
         assert_eq!(prs.unary(), Ok( Expression::Unary {
             operator: Token::Minus(position(1, 23)),
             rhs: Box::new(Expression::Literal(
                 Token::Integer{start: position(1,24), end: position(1,25),
-                    source:"42".to_string(), value: 42, base: IntegerBase::Decimal}))}));
+                    source:"42".to_string(), digits: "42".to_string(), value: 42, base: IntegerBase::Decimal, negative: false}))}));
+    }
+
+    #[test]
+    fn test_expression_cast() {
+        let txt = "x as u8";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.expression(), Ok( Expression::Cast {
+            expr: Box::new(Expression::Identifier(
+                Token::Identifier{start: position(1,1), end: position(1,1), source: "x".to_string()})),
+            target: TypeRef::Primitive(Token::KwTypeU8(position(1, 6))),
+        }));
+    }
+
+    #[test]
+    fn test_expression_precedence_climbing() {
+        let mut prs = Parser::create("1 + 2 * 3 - 4".to_string().into_bytes());
+        assert_eq!(prs.expression(), Ok( Expression::Binary {
+            lhs: Box::new(Expression::Binary {
+                lhs: Box::new(integer(1, 1, 1)),
+                operator: Token::Plus(position(1, 3)),
+                rhs: Box::new(Expression::Binary {
+                    lhs: Box::new(integer(1, 5, 2)),
+                    operator: Token::Star(position(1, 7)),
+                    rhs: Box::new(integer(1, 9, 3)),
+                }),
+            }),
+            operator: Token::Minus(position(1, 11)),
+            rhs: Box::new(integer(1, 13, 4)),
+        }));
+
+        let mut prs = Parser::create("a = b += c".to_string().into_bytes());
+        assert_eq!(prs.expression(), Ok( Expression::Assign {
+            target: Box::new(identifier(1, 1, "a")),
+            operator: Token::Assign(position(1, 3)),
+            value: Box::new(Expression::Assign {
+                target: Box::new(identifier(1, 5, "b")),
+                operator: Token::AddAssign(position(1, 7)),
+                value: Box::new(identifier(1, 10, "c")),
+            }),
+        }));
+    }
+
+    #[test]
+    fn test_new_from_tokens() {
+        let literal = |expr| match expr { Expression::Literal(token) => token, _ => unreachable!() };
+        let tokens = vec![literal(integer(1, 1, 1)), Token::Plus(position(1, 3)), literal(integer(1, 5, 2))];
+
+        let mut prs = Parser::new_from_tokens(tokens);
+        assert_eq!(prs.parse_expression_complete(), Ok( Expression::Binary {
+            lhs: Box::new(integer(1, 1, 1)),
+            operator: Token::Plus(position(1, 3)),
+            rhs: Box::new(integer(1, 5, 2)),
+        }));
+    }
+
+    #[test]
+    fn test_expression_cast_precedence() {
+        let txt = "1 + 2 as i64";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.expression(), Ok( Expression::Binary {
+            lhs: Box::new(Expression::Literal(
+                Token::Integer{start: position(1,1), end: position(1,1),
+                    source: "1".to_string(), digits: "1".to_string(), value: 1, base: IntegerBase::Decimal, negative: false })),
+            operator: Token::Plus(position(1, 3)),
+            rhs: Box::new(Expression::Cast {
+                expr: Box::new(Expression::Literal(
+                    Token::Integer{start: position(1,5), end: position(1,5),
+                        source: "2".to_string(), digits: "2".to_string(), value: 2, base: IntegerBase::Decimal, negative: false })),
+                target: TypeRef::Primitive(Token::KwTypeI64(position(1, 10))),
+            }),
+        }));
+    }
+
+    #[test]
+    fn test_expression_block() {
+        let txt = "{ let x = 1; x }";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.expression(), Ok( Expression::Block {
+            open: position(1, 1),
+            statements: vec![Statement::Let {
+                name: Token::Identifier{start: position(1,7), end: position(1,7), source: "x".to_string()},
+                mutable: false,
+                type_ref: None,
+                value: Some(Expression::Literal(
+                    Token::Integer{start: position(1,11), end: position(1,11),
+                        source: "1".to_string(), digits: "1".to_string(), value: 1, base: IntegerBase::Decimal, negative: false })),
+            }],
+            tail: Some(Box::new(Expression::Identifier(
+                Token::Identifier{start: position(1,14), end: position(1,14), source: "x".to_string()}))),
+            close: position(1, 16),
+        }));
+    }
+
+    #[test]
+    fn test_expression_block_empty_and_nested() {
+        let txt = "{} { {} }";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.expression(), Ok( empty_block(1, 1) ));
+        assert_eq!(prs.expression(), Ok( Expression::Block {
+            open: position(1, 4),
+            statements: vec![],
+            tail: Some(Box::new(empty_block(1, 6))),
+            close: position(1, 9),
+        }));
+    }
+
+    #[test]
+    fn test_expression_block_missing_brace() {
+        let txt = "{ let x = 1; x";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.expression(), Err(ParseError::MissingToken{
+            message: "Missing closing brace for opening brace (line: 1, column: 1).".to_string(),
+            pos: position(1, 1) }));
+    }
+
+    fn identifier(line: u32, column: u32, name: &str) -> Expression {
+        Expression::Identifier(identifier_token(line, column, name))
+    }
+
+    fn identifier_token(line: u32, column: u32, name: &str) -> Token {
+        Token::Identifier{start: position(line, column),
+            end: position(line, column + name.len() as u32 - 1), source: name.to_string()}
+    }
+
+    fn integer(line: u32, column: u32, value: u128) -> Expression {
+        let source = value.to_string();
+        Expression::Literal(Token::Integer{start: position(line, column),
+            end: position(line, column + source.len() as u32 - 1), digits: source.clone(), source, value,
+            base: IntegerBase::Decimal, negative: false})
+    }
+
+    /// Returns the block `{ tail }` written on the line of the tail.
+    fn block(tail: Expression) -> Box<Expression> {
+        let span = tail.span().unwrap();
+        Box::new(Expression::Block { open: position(span.start.line, span.start.column - 2), statements: vec![],
+            tail: Some(Box::new(tail)), close: span.end.advanced(2) })
+    }
+
+    /// Returns the block `{}` starting at the given position.
+    fn empty_block(line: u32, column: u32) -> Expression {
+        Expression::Block { open: position(line, column), statements: vec![], tail: None,
+            close: position(line, column + 1) }
+    }
+
+    #[test]
+    fn test_expression_if_else() {
+        let txt = "if a { 1 } else { 2 }";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.expression(), Ok( Expression::If {
+            keyword: position(1, 1),
+            cond: Box::new(identifier(1, 4, "a")),
+            then_block: block(integer(1, 8, 1)),
+            else_block: Some(block(integer(1, 19, 2))),
+        }));
+    }
+
+    #[test]
+    fn test_expression_else_if_chain() {
+        let txt = "if a { 1 } else if b { 2 } else { 3 }";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.expression(), Ok( Expression::If {
+            keyword: position(1, 1),
+            cond: Box::new(identifier(1, 4, "a")),
+            then_block: block(integer(1, 8, 1)),
+            else_block: Some(Box::new(Expression::If {
+                keyword: position(1, 17),
+                cond: Box::new(identifier(1, 20, "b")),
+                then_block: block(integer(1, 24, 2)),
+                else_block: Some(block(integer(1, 35, 3))),
+            })),
+        }));
+    }
+
+    #[test]
+    fn test_expression_if_missing_parts() {
+        let mut prs = Parser::create("if { 1 }".to_string().into_bytes());
+        assert_eq!(prs.expression(), Err(ParseError::MissingToken{
+            message: "Missing condition for 'if' (line: 1, column: 4).".to_string(),
+            pos: position(1, 4) }));
+
+        let mut prs = Parser::create("if a 1".to_string().into_bytes());
+        assert!(prs.expression().is_err());
+    }
+
+    #[test]
+    fn test_expression_match() {
+        let mut prs = Parser::create("match x { 1 => a, _ => b, }".to_string().into_bytes());
+        assert_eq!(prs.expression(), Ok( Expression::Match {
+            keyword: position(1, 1),
+            scrutinee: Box::new(identifier(1, 7, "x")),
+            arms: vec![
+                (Pattern::Literal(Token::Integer{start: position(1, 11), end: position(1, 11),
+                    source: "1".to_string(), digits: "1".to_string(), value: 1, base: IntegerBase::Decimal, negative: false}),
+                    identifier(1, 16, "a")),
+                (Pattern::Wildcard(position(1, 19)), identifier(1, 24, "b")),
+            ],
+            close: position(1, 27),
+        }));
+
+        let mut prs = Parser::create("match x { y => y }".to_string().into_bytes());
+        assert_eq!(prs.expression(), Ok( Expression::Match {
+            keyword: position(1, 1),
+            scrutinee: Box::new(identifier(1, 7, "x")),
+            arms: vec![(Pattern::Binding(identifier_token(1, 11, "y")), identifier(1, 16, "y"))],
+            close: position(1, 18),
+        }));
+    }
+
+    #[test]
+    fn test_expression_match_missing_parts() {
+        let mut prs = Parser::create("match x { 1 a }".to_string().into_bytes());
+        assert_eq!(prs.expression(), Err(ParseError::MissingToken{
+            message: "Missing '=>' after match pattern (line: 1, column: 13).".to_string(),
+            pos: position(1, 13) }));
+
+        let mut prs = Parser::create("match { 1 => a }".to_string().into_bytes());
+        assert_eq!(prs.expression(), Err(ParseError::MissingToken{
+            message: "Missing expression for 'match' (line: 1, column: 7).".to_string(),
+            pos: position(1, 7) }));
+
+        let mut prs = Parser::create("match x { 1 => a 2 => b }".to_string().into_bytes());
+        assert!(prs.expression().is_err());
+    }
+
+    #[test]
+    fn test_statement_while() {
+        let txt = "while a { b; }";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.statement(), Ok( Statement::While {
+            cond: identifier(1, 7, "a"),
+            body: Expression::Block {
+                open: position(1, 9),
+                statements: vec![Statement::Expr(identifier(1, 11, "b"))],
+                tail: None,
+                close: position(1, 14),
+            },
+        }));
+    }
+
+    #[test]
+    fn test_statement_break() {
+        let txt = "while true { break; continue; }";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.statement(), Ok( Statement::While {
+            cond: Expression::Literal(Token::KwTrue(position(1, 7))),
+            body: Expression::Block {
+                open: position(1, 12),
+                statements: vec![Statement::Break(Token::KwBreak(position(1, 14)), None),
+                                 Statement::Continue(Token::KwContinue(position(1, 21)))],
+                tail: None,
+                close: position(1, 31),
+            },
+        }));
+
+        let mut prs = Parser::create("{ break; }".to_string().into_bytes());
+        assert!(prs.expression().is_err());
+    }
+
+    #[test]
+    fn test_statement_loop() {
+        let mut prs = Parser::create("loop {} loop { break; }".to_string().into_bytes());
+        assert_eq!(prs.statement(), Ok( Statement::Loop {
+            body: empty_block(1, 6),
+        }));
+        assert_eq!(prs.statement(), Ok( Statement::Loop {
+            body: Expression::Block {
+                open: position(1, 14),
+                statements: vec![Statement::Break(Token::KwBreak(position(1, 16)), None)],
+                tail: None,
+                close: position(1, 23),
+            },
+        }));
+
+        let mut prs = Parser::create("loop { break x + 1; }".to_string().into_bytes());
+        assert_eq!(prs.statement(), Ok( Statement::Loop {
+            body: Expression::Block {
+                open: position(1, 6),
+                statements: vec![Statement::Break(Token::KwBreak(position(1, 8)), Some(Expression::Binary {
+                    lhs: Box::new(identifier(1, 14, "x")),
+                    operator: Token::Plus(position(1, 16)),
+                    rhs: Box::new(integer(1, 18, 1)),
+                }))],
+                tail: None,
+                close: position(1, 21),
+            },
+        }));
+
+        let mut prs = Parser::create("loop { while a { break 1; } }".to_string().into_bytes());
+        assert_eq!(prs.statement(), Err(ParseError::BreakValueOutsideLoop{
+            message: "'break' with a value is only allowed inside 'loop' (line: 1, column: 18).".to_string(),
+            pos: position(1, 18) }));
+    }
+
+    #[test]
+    fn test_statement_for() {
+        let mut prs = Parser::create("for i in 0..10 { }".to_string().into_bytes());
+        assert_eq!(prs.statement(), Ok( Statement::For {
+            binding: identifier_token(1, 5, "i"),
+            iter: Expression::Binary {
+                lhs: Box::new(integer(1, 10, 0)),
+                operator: Token::Range(position(1, 11)),
+                rhs: Box::new(integer(1, 13, 10)),
+            },
+            body: Expression::Block { open: position(1, 16), statements: vec![], tail: None, close: position(1, 18) },
+        }));
+
+        let mut prs = Parser::create("for i in 0x0..0b10 { }".to_string().into_bytes());
+        assert_eq!(prs.statement(), Ok( Statement::For {
+            binding: identifier_token(1, 5, "i"),
+            iter: Expression::Binary {
+                lhs: Box::new(Expression::Literal(Token::Integer{ start: position(1, 10), end: position(1, 12),
+                    source: "0x0".to_string(), digits: "0x0".to_string(), value: 0, base: IntegerBase::Hexadecimal, negative: false })),
+                operator: Token::Range(position(1, 13)),
+                rhs: Box::new(Expression::Literal(Token::Integer{ start: position(1, 15), end: position(1, 18),
+                    source: "0b10".to_string(), digits: "0b10".to_string(), value: 2, base: IntegerBase::Binary, negative: false })),
+            },
+            body: Expression::Block { open: position(1, 20), statements: vec![], tail: None, close: position(1, 22) },
+        }));
+
+        let mut prs = Parser::create("for i 0..10 { }".to_string().into_bytes());
+        assert_eq!(prs.statement(), Err(ParseError::MissingToken{
+            message: "Missing 'in' after loop variable (line: 1, column: 7).".to_string(),
+            pos: position(1, 7) }));
+    }
+
+    #[test]
+    fn test_statement_return() {
+        let txt = "return; return 1 + 2;";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.statement(), Ok( Statement::Return(None)));
+        assert_eq!(prs.statement(), Ok( Statement::Return(Some(Expression::Binary {
+            lhs: Box::new(integer(1, 16, 1)),
+            operator: Token::Plus(position(1, 18)),
+            rhs: Box::new(integer(1, 20, 2)),
+        }))));
+    }
+
+    #[test]
+    fn test_statement_return_missing_semicolon() {
+        let mut prs = Parser::create("return 1 }".to_string().into_bytes());
+        assert_eq!(prs.statement(), Err(ParseError::MissingToken{
+            message: "Missing semicolon after return statement (line: 1, column: 10).".to_string(),
+            pos: position(1, 10) }));
+    }
+
+    #[test]
+    fn test_statement_expect() {
+        let txt = "expect x == 1; expect y : \"y must hold\";";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.statement(), Ok( Statement::Expect {
+            condition: Expression::Binary {
+                lhs: Box::new(identifier(1, 8, "x")),
+                operator: Token::Equals(position(1, 10)),
+                rhs: Box::new(integer(1, 13, 1)),
+            },
+            implication: None,
+            message: None,
+        }));
+        assert_eq!(prs.statement(), Ok( Statement::Expect {
+            condition: identifier(1, 23, "y"),
+            implication: None,
+            message: Some("y must hold".to_string()),
+        }));
+    }
+
+    #[test]
+    fn test_statement_expect_implies() {
+        let txt = "expect x > 0 => y < 10; expect a => b : \"b if a\";";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.statement(), Ok( Statement::Expect {
+            condition: Expression::Binary {
+                lhs: Box::new(identifier(1, 8, "x")),
+                operator: Token::Greater(position(1, 10)),
+                rhs: Box::new(integer(1, 12, 0)),
+            },
+            implication: Some( Expression::Binary {
+                lhs: Box::new(identifier(1, 17, "y")),
+                operator: Token::Less(position(1, 19)),
+                rhs: Box::new(integer(1, 21, 10)),
+            }),
+            message: None,
+        }));
+        assert_eq!(prs.statement(), Ok( Statement::Expect {
+            condition: identifier(1, 32, "a"),
+            implication: Some( identifier(1, 37, "b") ),
+            message: Some("b if a".to_string()),
+        }));
+
+        let mut prs = Parser::create("expect x => y".to_string().into_bytes());
+        assert_eq!(prs.statement(), Err(ParseError::MissingToken{
+            message: "Missing semicolon after expect statement (line: 1, column: 13).".to_string(),
+            pos: position(1, 13) }));
+    }
+
+    #[test]
+    fn test_statement_expect_message_escapes() {
+        let mut prs = Parser::create("expect x : \"bad \\u{26A0}\\n\";".to_string().into_bytes());
+        assert_eq!(prs.statement(), Ok( Statement::Expect {
+            condition: identifier(1, 8, "x"),
+            implication: None,
+            message: Some("bad \u{26A0}\n".to_string()),
+        }));
+    }
+
+    #[test]
+    fn test_statement_expect_missing_semicolon() {
+        let mut prs = Parser::create("expect x".to_string().into_bytes());
+        assert_eq!(prs.statement(), Err(ParseError::MissingToken{
+            message: "Missing semicolon after expect statement (line: 1, column: 8).".to_string(),
+            pos: position(1, 8) }));
+    }
+
+    #[test]
+    fn test_item_struct() {
+        let txt = "struct Point { x: i32, y: Coord, }";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.item(), Ok( Item {
+            doc: vec![],
+            attributes: vec![],
+            kind: ItemKind::Struct {
+                name: identifier_token(1, 8, "Point"),
+                fields: vec![
+                    Field{ name: identifier_token(1, 16, "x"),
+                           type_ref: TypeRef::Primitive(Token::KwTypeI32(position(1, 19))) },
+                    Field{ name: identifier_token(1, 24, "y"),
+                           type_ref: TypeRef::Named(identifier_token(1, 27, "Coord")) },
+                ],
+            },
+        }));
+    }
+
+    #[test]
+    fn test_item_struct_duplicate_field() {
+        let mut prs = Parser::create("struct S { a: i32, a: i32 }".to_string().into_bytes());
+        assert_eq!(prs.item(), Err(ParseError::DuplicateField{
+            message: "Duplicate field 'a' (line: 1, column: 20), first declared at (line: 1, column: 12).".to_string(),
+            name: "a".to_string(), first: position(1, 12), second: position(1, 20) }));
+    }
+
+    #[test]
+    fn test_item_doc_comments() {
+        let txt = concat!(
+            "/// A point.\n",
+            "// Not used anywhere.\n",
+            "struct Point { x: i32 // the x coordinate\n }\n",
+            "struct Empty {}");
+
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+        prs.set_collect_comments(true);
+        let item = prs.item().unwrap();
+        assert_eq!(item.doc, vec![" A point.".to_string(), " Not used anywhere.".to_string()]);
+        assert_eq!(prs.item().unwrap().doc, Vec::<String>::new());
+
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+        assert_eq!(prs.item().unwrap().doc, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_expression_assignment() {
+        let txt = "a <<= b >>= 2";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.expression(), Ok( Expression::Assign {
+            target: Box::new(identifier(1, 1, "a")),
+            operator: Token::ShlAssign(position(1, 3)),
+            value: Box::new(Expression::Assign {
+                target: Box::new(identifier(1, 7, "b")),
+                operator: Token::ShrAssign(position(1, 9)),
+                value: Box::new(integer(1, 13, 2)),
+            }),
+        }));
+    }
+
+    #[test]
+    fn test_expression_assignment_targets() {
+        assert_eq!("a.b = 1".parse::<Expression>(), Ok( Expression::Assign {
+            target: Box::new(Expression::Member { object: Box::new(identifier(1, 1, "a")),
+                name: identifier_token(1, 3, "b") }),
+            operator: Token::Assign(position(1, 5)),
+            value: Box::new(integer(1, 7, 1)),
+        }));
+        assert_eq!("arr[i] += 2".parse::<Expression>(), Ok( Expression::Assign {
+            target: Box::new(Expression::Index { object: Box::new(identifier(1, 1, "arr")),
+                index: Box::new(identifier(1, 5, "i")), close: position(1, 6) }),
+            operator: Token::AddAssign(position(1, 8)),
+            value: Box::new(integer(1, 11, 2)),
+        }));
+        assert!("*p = x".parse::<Expression>().is_ok());
+
+        assert_eq!("1 = 2".parse::<Expression>(), Err(ParseError::InvalidAssignTarget{
+            message: "Invalid left-hand side of '=' (line: 1, column: 3).".to_string(), pos: position(1, 3) }));
+        assert!(std::matches!("a + b -= 1".parse::<Expression>(), Err(ParseError::InvalidAssignTarget{..})));
+    }
+
+    #[test]
+    fn test_item_attributes() {
+        let txt = "#[inline]\n#[cfg(test)]\nfn check(a: i32) -> bool { true }";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+
+        assert_eq!(prs.item(), Ok( Item {
+            doc: vec![],
+            attributes: vec![
+                Attribute{ path: vec!["inline".to_string()], args: vec![] },
+                Attribute{ path: vec!["cfg".to_string()], args: vec![identifier_token(2, 7, "test")] },
+            ],
+            kind: ItemKind::Function {
+                name: identifier_token(3, 4, "check"),
+                params: vec![ Field{ name: identifier_token(3, 10, "a"),
+                                     type_ref: TypeRef::Primitive(Token::KwTypeI32(position(3, 13))) } ],
+                return_type: Some(TypeRef::Primitive(Token::KwTypeBool(position(3, 21)))),
+                body: *block(Expression::Literal(Token::KwTrue(position(3, 28)))),
+            },
+        }));
+    }
+
+    #[test]
+    fn test_item_attribute_unterminated() {
+        let mut prs = Parser::create("#[inline fn f() {}".to_string().into_bytes());
+        assert_eq!(prs.item(), Err(ParseError::MissingToken{
+            message: "Unterminated attribute, expected ']' (line: 1, column: 10).".to_string(),
+            pos: position(1, 10) }));
+
+        let mut prs = Parser::create("#[cfg(test".to_string().into_bytes());
+        assert!(prs.item().is_err());
+    }
+
+    #[test]
+    fn test_expression_chained_comparison() {
+        let mut prs = Parser::create("a < b < c".to_string().into_bytes());
+        assert!(prs.expression().is_ok());
+
+        let mut prs = Parser::create("a < b < c".to_string().into_bytes());
+        prs.set_reject_chained_comparisons(true);
+        assert_eq!(prs.expression(), Err(ParseError::ChainedComparison{
+            message: "Chained comparison requires parentheses (line: 1, column: 7).".to_string(),
+            pos: position(1, 7) }));
+
+        let mut prs = Parser::create("a == b != c".to_string().into_bytes());
+        prs.set_reject_chained_comparisons(true);
+        assert!(std::matches!(prs.expression(), Err(ParseError::ChainedComparison{..})));
+
+        for txt in ["a < b == c", "a == b < c", "a == b + 1 >= c"] {
+            let mut prs = Parser::create(txt.to_string().into_bytes());
+            prs.set_reject_chained_comparisons(true);
+            assert!(std::matches!(prs.expression(), Err(ParseError::ChainedComparison{..})), "{}", txt);
+        }
+        for txt in ["(a < b) == c", "a == (b < c)", "a + 1 < b", "x = a < b"] {
+            let mut prs = Parser::create(txt.to_string().into_bytes());
+            prs.set_reject_chained_comparisons(true);
+            assert!(prs.expression().is_ok(), "{}", txt);
+        }
+
+        let mut prs = Parser::create("(a < b) < c".to_string().into_bytes());
+        prs.set_reject_chained_comparisons(true);
+        assert_eq!(prs.expression(), Ok( Expression::Binary {
+            lhs: Box::new(Expression::Binary {
+                lhs: Box::new(identifier(1, 2, "a")),
+                operator: Token::Less(position(1, 4)),
+                rhs: Box::new(identifier(1, 6, "b")),
+            }),
+            operator: Token::Less(position(1, 9)),
+            rhs: Box::new(identifier(1, 11, "c")),
+        }));
+    }
+
+    #[test]
+    fn test_fold_unary_minus_literals() {
+        let txt = "-42 + -1.5";
+        let mut prs = Parser::create(txt.to_string().into_bytes());
+        prs.set_fold_unary_minus_literals(true);
+        assert_eq!(prs.expression(), Ok( Expression::Binary {
+            lhs: Box::new(Expression::Literal(Token::Integer{ start: position(1, 1), end: position(1, 3),
+                source: "-42".to_string(), digits: "-42".to_string(), value: 42, base: IntegerBase::Decimal, negative: true })),
+            operator: Token::Plus(position(1, 5)),
+            rhs: Box::new(Expression::Literal(Token::FloatNumber{ start: position(1, 7), end: position(1, 10),
+                source: "-1.5".to_string(), digits: "-1.5".to_string(), value: -1.5 })),
+        }));
+
+        let mut prs = Parser::create("-9223372036854775808".to_string().into_bytes());
+        prs.set_fold_unary_minus_literals(true);
+        assert_eq!(prs.expression(), Ok( Expression::Literal(Token::Integer{ start: position(1, 1),
+            end: position(1, 20), source: "-9223372036854775808".to_string(), digits: "-9223372036854775808".to_string(), value: i64::MIN.unsigned_abs() as u128,
+            base: IntegerBase::Decimal, negative: true })));
+
+        let mut prs = Parser::create("-170141183460469231731687303715884105729".to_string().into_bytes());
+        prs.set_fold_unary_minus_literals(true);
+        assert!(std::matches!(prs.expression(), Ok( Expression::Unary{..} )));
+
+        let mut prs = Parser::create("- -1".to_string().into_bytes());
+        prs.set_fold_unary_minus_literals(true);
+        assert_eq!(prs.expression(), Ok( Expression::Unary {
+            operator: Token::Minus(position(1, 1)),
+            rhs: Box::new(Expression::Literal(Token::Integer{ start: position(1, 3), end: position(1, 4),
+                source: "-1".to_string(), digits: "-1".to_string(), value: 1, base: IntegerBase::Decimal, negative: true })),
+        }));
+
+        let mut prs = Parser::create("-42".to_string().into_bytes());
+        assert_eq!(prs.expression(), Ok( Expression::Unary {
+            operator: Token::Minus(position(1, 1)),
+            rhs: Box::new(integer(1, 2, 42)),
+        }));
+    }
+
+    #[test]
+    fn test_parse_expression_complete() {
+        let mut prs = Parser::create("1 + 2".to_string().into_bytes());
+        assert_eq!(prs.parse_expression_complete(), Ok( Expression::Binary {
+            lhs: Box::new(integer(1, 1, 1)),
+            operator: Token::Plus(position(1, 3)),
+            rhs: Box::new(integer(1, 5, 2)),
+        }));
+
+        let mut prs = Parser::create("1 + 2 garbage".to_string().into_bytes());
+        assert_eq!(prs.parse_expression_complete(), Err(ParseError::TrailingTokens{
+            message: "Unexpected 'garbage' after expression (line: 1, column: 7).".to_string(),
+            pos: position(1, 7) }));
+    }
+
+    #[test]
+    fn test_identifier() {
+        let mut prs = Parser::create("foo 123".to_string().into_bytes());
+        assert_eq!(prs.identifier("identifier"), Ok( identifier_token(1, 1, "foo") ));
+        assert_eq!(prs.identifier("identifier"), Err(ParseError::MissingToken{
+            message: "Expected identifier (line: 1, column: 5).".to_string(), pos: position(1, 5) }));
+    }
+
+    #[test]
+    fn test_keyword_as_identifier() {
+        let mut prs = Parser::create("let struct = 1;".to_string().into_bytes());
+        assert_eq!(prs.statement(), Err(ParseError::KeywordAsIdentifier{
+            message: "Keyword 'struct' cannot be used as identifier (line: 1, column: 5).".to_string(),
+            keyword: "struct".to_string(), pos: position(1, 5) }));
+
+        let mut prs = Parser::create("fn f(x: i32, fn: i32) {}".to_string().into_bytes());
+        assert_eq!(prs.item().unwrap_err().message(),
+            "Keyword 'fn' cannot be used as parameter name (line: 1, column: 14).");
+    }
+
+    #[test]
+    fn test_from_str() {
+        let expr: Result<Expression, ParseError> = "a * 2".parse();
+        assert_eq!(expr, Ok( Expression::Binary {
+            lhs: Box::new(Expression::Identifier(Token::Identifier{start: position(1, 1),
+                end: position(1, 1), source: "a".to_string()})),
+            operator: Token::Star(position(1, 3)),
+            rhs: Box::new(integer(1, 5, 2)),
+        }));
+
+        let err = "a * 2 )".parse::<Expression>().unwrap_err();
+        assert_eq!(err.to_string(), "Unexpected ')' after expression (line: 1, column: 7).");
+        assert!(std::matches!(err, ParseError::TrailingTokens{..}));
+    }
+
+    #[test]
+    fn test_expression_missing_paren_position() {
+        let mut prs = Parser::create("(1 + 2;".to_string().into_bytes());
+        match prs.expression() {
+            Err(ParseError::MissingToken{ pos, .. }) => assert_eq!(pos, position(1, 1)),
+            r => panic!("unexpected result {:?}", r),
+        }
+
+        let mut prs = Parser::create("f(a".to_string().into_bytes());
+        assert_eq!(prs.expression(), Err(ParseError::MissingToken{
+            message: "Missing closing parentheses for call arguments (line: 1, column: 2).".to_string(),
+            pos: position(1, 2) }));
+
+        // other missing tokens are located at the start of the token found instead
+        let mut prs = Parser::create("let a = 1;".to_string().into_bytes());
+        assert_eq!(prs.parse_expression_complete(), Err(ParseError::MissingToken{
+            message: "Expected literal (line: 1, column: 1).".to_string(), pos: position(1, 1) }));
+    }
+
+    #[test]
+    fn test_expression_try() {
+        let mut prs = Parser::create("x?".to_string().into_bytes());
+        assert_eq!(prs.expression(), Ok( Expression::Try { expr: Box::new(identifier(1, 1, "x")) }));
+
+        let mut prs = Parser::create("a?.b?".to_string().into_bytes());
+        assert_eq!(prs.expression(), Ok( Expression::Try {
+            expr: Box::new(Expression::Member {
+                object: Box::new(Expression::Try { expr: Box::new(identifier(1, 1, "a")) }),
+                name: identifier_token(1, 4, "b"),
+            }),
+        }));
+    }
+
+    #[test]
+    fn test_expression_postfix_chain() {
+        let mut prs = Parser::create("f()?.g(1, x)[2]".to_string().into_bytes());
+        assert_eq!(prs.expression(), Ok( Expression::Index {
+            object: Box::new(Expression::Call {
+                callee: Box::new(Expression::Member {
+                    object: Box::new(Expression::Try {
+                        expr: Box::new(Expression::Call { callee: Box::new(identifier(1, 1, "f")), args: vec![],
+                            close: position(1, 3) }),
+                    }),
+                    name: identifier_token(1, 6, "g"),
+                }),
+                args: vec![integer(1, 8, 1), identifier(1, 11, "x")],
+                close: position(1, 12),
+            }),
+            index: Box::new(integer(1, 14, 2)),
+            close: position(1, 15),
+        }));
+
+        let mut prs = Parser::create("-a?".to_string().into_bytes());
+        assert_eq!(prs.expression(), Ok( Expression::Unary {
+            operator: Token::Minus(position(1, 1)),
+            rhs: Box::new(Expression::Try { expr: Box::new(identifier(1, 2, "a")) }),
+        }));
+
+        let mut prs = Parser::create("f(1".to_string().into_bytes());
+        assert!(prs.expression().is_err());
+    }
+
+    #[test]
+    fn test_expression_postfix_on_literal_and_block() {
+        let mut prs = Parser::create("\"s\".len()".to_string().into_bytes());
+        assert_eq!(prs.parse_expression_complete(), Ok( Expression::Call {
+            callee: Box::new(Expression::Member {
+                object: Box::new(Expression::Literal(
+                    Token::String{start: position(1, 1), end: position(1, 3), source: "s".to_string()})),
+                name: identifier_token(1, 5, "len"),
+            }),
+            args: vec![],
+            close: position(1, 9),
+        }));
+
+        let mut prs = Parser::create("{ a }.x".to_string().into_bytes());
+        assert_eq!(prs.parse_expression_complete(), Ok( Expression::Member {
+            object: block(identifier(1, 3, "a")),
+            name: identifier_token(1, 7, "x"),
+        }));
+
+        let mut prs = Parser::create("(1)?".to_string().into_bytes());
+        assert_eq!(prs.parse_expression_complete(), Ok( Expression::Try { expr: Box::new(integer(1, 2, 1)) }));
+
+        let mut prs = Parser::create("match x { _ => 1 }.y".to_string().into_bytes());
+        assert!(std::matches!(prs.parse_expression_complete(), Ok( Expression::Member{..} )));
+
+        // a block statement is not called or indexed by what follows, but takes members
+        let mut prs = Parser::create("{ a } (b); { c }.d(e);".to_string().into_bytes());
+        assert_eq!(prs.parse_statements(), Ok( vec![
+            Statement::Expr(*block(identifier(1, 3, "a"))),
+            Statement::Expr(identifier(1, 8, "b")),
+            Statement::Expr(Expression::Call {
+                callee: Box::new(Expression::Member {
+                    object: block(identifier(1, 14, "c")),
+                    name: identifier_token(1, 18, "d"),
+                }),
+                args: vec![identifier(1, 20, "e")],
+                close: position(1, 21),
+            }),
+        ]));
+    }
+
+    #[test]
+    fn test_expression_tuple() {
+        assert_eq!("()".parse::<Expression>(), Ok( Expression::Tuple{ open: position(1, 1), elements: vec![],
+            close: position(1, 2) } ));
+        assert_eq!("(a)".parse::<Expression>(), Ok( identifier(1, 2, "a") ));
+        assert_eq!("(a, b)".parse::<Expression>(), Ok( Expression::Tuple{ open: position(1, 1), elements: vec![
+            identifier(1, 2, "a"), identifier(1, 5, "b")], close: position(1, 6) } ));
+        assert_eq!("(a,)".parse::<Expression>(), Ok( Expression::Tuple{ open: position(1, 1),
+            elements: vec![identifier(1, 2, "a")], close: position(1, 4) } ));
+
+        let mut prs = Parser::create("(a, b".to_string().into_bytes());
+        assert_eq!(prs.expression(), Err(ParseError::MissingToken{
+            message: "Missing closing parentheses for opening parentheses (line: 1, column: 1).".to_string(),
+            pos: position(1, 1) }));
+    }
+
+    #[test]
+    fn test_expression_ref_deref() {
+        assert_eq!("&x".parse::<Expression>(), Ok( Expression::Ref { operator: Token::Ampersand(position(1, 1)),
+            mutable: false, expr: Box::new(identifier(1, 2, "x")) }));
+        assert_eq!("&mut x".parse::<Expression>(), Ok( Expression::Ref { operator: Token::Ampersand(position(1, 1)),
+            mutable: true, expr: Box::new(identifier(1, 6, "x")) }));
+        assert_eq!("*p".parse::<Expression>(), Ok( Expression::Deref { operator: Token::Star(position(1, 1)),
+            expr: Box::new(identifier(1, 2, "p")) }));
+        assert_eq!("*p.x".parse::<Expression>(), Ok( Expression::Deref { operator: Token::Star(position(1, 1)),
+            expr: Box::new(Expression::Member { object: Box::new(identifier(1, 2, "p")), name: identifier_token(1, 4, "x") }) }));
+        assert_eq!("a * *b".parse::<Expression>(), Ok( Expression::Binary {
+            lhs: Box::new(identifier(1, 1, "a")),
+            operator: Token::Star(position(1, 3)),
+            rhs: Box::new(Expression::Deref { operator: Token::Star(position(1, 5)), expr: Box::new(identifier(1, 6, "b")) }),
+        }));
+
+        let txt = "&&mut x";
+        let expr: Expression = txt.parse().unwrap();
+        assert_eq!(expr, Expression::Ref { operator: Token::Ampersand(position(1, 1)), mutable: false,
+            expr: Box::new(Expression::Ref { operator: Token::Ampersand(position(1, 2)), mutable: true,
+                expr: Box::new(identifier(1, 7, "x")) }) });
+        assert_eq!(expr.source_span(txt.as_bytes()), Some(txt));
+    }
+
+    #[test]
+    fn test_split_shr_into_two_gt() {
+        let mut prs = Parser::create("a<b<c>> >".to_string().into_bytes());
+        for _ in 0..5 {
+            prs.advance();
+        }
+        assert_eq!(prs.split_shr_into_two_gt(), Some( Token::Greater(position(1, 6)) ));
+        assert_eq!(prs.split_shr_into_two_gt(), None);
+        assert_eq!(prs.tokens.next(), Ok( Token::Greater(position(1, 7)) ));
+        assert_eq!(prs.split_shr_into_two_gt(), None);
+        assert_eq!(prs.tokens.next(), Ok( Token::Greater(position(1, 9)) ));
+    }
+
+    #[test]
+    fn test_expression_prefix_postfix_precedence() {
+        assert_eq!("-a.b".parse::<Expression>(), Ok( Expression::Unary {
+            operator: Token::Minus(position(1, 1)),
+            rhs: Box::new(Expression::Member { object: Box::new(identifier(1, 2, "a")),
+                name: identifier_token(1, 4, "b") }),
+        }));
+        assert_eq!("!f()".parse::<Expression>(), Ok( Expression::Unary {
+            operator: Token::ExclamationMark(position(1, 1)),
+            rhs: Box::new(Expression::Call { callee: Box::new(identifier(1, 2, "f")), args: vec![],
+                close: position(1, 4) }),
+        }));
+        assert_eq!("~arr[0]".parse::<Expression>(), Ok( Expression::Unary {
+            operator: Token::Tilde(position(1, 1)),
+            rhs: Box::new(Expression::Index { object: Box::new(identifier(1, 2, "arr")),
+                index: Box::new(integer(1, 6, 0)), close: position(1, 7) }),
+        }));
+        assert_eq!("-a.b() as i32".parse::<Expression>(), Ok( Expression::Cast {
+            expr: Box::new(Expression::Unary {
+                operator: Token::Minus(position(1, 1)),
+                rhs: Box::new(Expression::Call {
+                    callee: Box::new(Expression::Member { object: Box::new(identifier(1, 2, "a")),
+                        name: identifier_token(1, 4, "b") }),
+                    args: vec![],
+                    close: position(1, 6),
+                }),
+            }),
+            target: TypeRef::Primitive(Token::KwTypeI32(position(1, 11))),
+        }));
+    }
+
+    #[test]
+    fn test_nesting_too_deep() {
+        let txt = format!("{}x{}", "(".repeat(1000), ")".repeat(1000));
+        let mut prs = Parser::create(txt.into_bytes());
+        assert!(std::matches!(prs.expression(), Err(ParseError::NestingTooDeep{..})));
+
+        let mut prs = Parser::create(format!("{}x", "-".repeat(1000)).into_bytes());
+        assert!(std::matches!(prs.expression(), Err(ParseError::NestingTooDeep{..})));
+
+        let mut prs = Parser::create(format!("{}1", "a = ".repeat(10_000)).into_bytes());
+        assert!(std::matches!(prs.expression(), Err(ParseError::NestingTooDeep{..})));
+
+        let mut prs = Parser::create(format!("if a {{}}{}", " else if a {}".repeat(10_000)).into_bytes());
+        assert!(std::matches!(prs.expression(), Err(ParseError::NestingTooDeep{..})));
+
+        let txt = format!("{}{}", "loop { ".repeat(10_000), "}".repeat(10_000));
+        let mut prs = Parser::create(txt.into_bytes());
+        assert!(std::matches!(prs.parse_statements(), Err(ParseError::NestingTooDeep{..})));
+
+        let mut prs = Parser::create("((x))".to_string().into_bytes());
+        prs.set_max_nesting_depth(3);
+        assert_eq!(prs.expression(), Ok( identifier(1, 3, "x") ));
+        let mut prs = Parser::create("(((x)))".to_string().into_bytes());
+        prs.set_max_nesting_depth(3);
+        assert_eq!(prs.expression(), Err(ParseError::NestingTooDeep{
+            message: "Expression nested too deeply (line: 1, column: 4).".to_string(), pos: position(1, 4) }));
+    }
+
+    #[test]
+    fn test_parse_statements() {
+        let mut prs = Parser::create("let x = 1; x + 2;".to_string().into_bytes());
+        assert_eq!(prs.parse_statements(), Ok( vec![
+            Statement::Let { name: identifier_token(1, 5, "x"), mutable: false, type_ref: None,
+                value: Some(integer(1, 9, 1)) },
+            Statement::Expr(Expression::Binary {
+                lhs: Box::new(identifier(1, 12, "x")),
+                operator: Token::Plus(position(1, 14)),
+                rhs: Box::new(integer(1, 16, 2)),
+            }),
+        ]));
+
+        let mut prs = Parser::create("if a { 1 } return; }".to_string().into_bytes());
+        let statements = prs.parse_statements().unwrap();
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[1], Statement::Return(None));
+        assert_eq!(prs.tokens.next(), Ok( Token::RightBrace(position(1, 20)) ));
+
+        let mut prs = Parser::create("x + 2".to_string().into_bytes());
+        assert!(std::matches!(prs.parse_statements(), Err(ParseError::MissingToken{..})));
     }
 }
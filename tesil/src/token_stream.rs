@@ -0,0 +1,264 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+use super::tokens::Token;
+use super::lexer::{Lexer, LexerError};
+use util::utf8::Position;
+
+/// Provider of the tokens read by a `TokenStream` with one token look-ahead, see `Lexer::peek()`
+/// and `Lexer::get()`.
+pub trait TokenSource {
+    fn peek(&mut self) -> Result<Token, LexerError>;
+    fn get(&mut self) -> Result<Token, LexerError>;
+
+    /// Returns the position up to which the tokens have been read including the look-ahead.
+    fn pos(&self) -> Position;
+
+    /// Returns the start of the peeked token, see `Lexer::next_pos()`.
+    fn next_pos(&self) -> Position;
+}
+
+impl TokenSource for Lexer {
+    fn peek(&mut self) -> Result<Token, LexerError> {
+        Lexer::peek(self)
+    }
+
+    fn get(&mut self) -> Result<Token, LexerError> {
+        Lexer::get(self)
+    }
+
+    fn pos(&self) -> Position {
+        Lexer::pos(self)
+    }
+
+    fn next_pos(&self) -> Position {
+        Lexer::next_pos(self)
+    }
+}
+
+/// Already scanned tokens, e.g. from `Lexer::tokenize_lossy` or built by hand, read without a
+/// lexer. After the last token `Token::EndOfFile` is returned at the end of the last token.
+pub struct TokenBuffer {
+    tokens: Vec<Token>,
+    next: usize,        // index of the next token
+    scanned: usize,     // number of tokens returned by 'peek()' or 'get()' so far
+}
+
+impl From<Vec<Token>> for TokenBuffer {
+    fn from(tokens: Vec<Token>) -> Self {
+        TokenBuffer{ tokens, next: 0, scanned: 0 }
+    }
+}
+
+impl TokenSource for TokenBuffer {
+    fn peek(&mut self) -> Result<Token, LexerError> {
+        self.scanned = self.scanned.max(self.next + 1).min(self.tokens.len());
+        Ok( self.tokens.get(self.next).cloned().unwrap_or_else(|| Token::EndOfFile(self.pos())) )
+    }
+
+    fn get(&mut self) -> Result<Token, LexerError> {
+        let token = self.peek();
+        self.next = (self.next + 1).min(self.tokens.len());
+        token
+    }
+
+    fn pos(&self) -> Position {
+        match self.scanned {
+            0 => Position{ line: 1, column: 0 },
+            n => self.tokens[n - 1].end_position(),
+        }
+    }
+
+    fn next_pos(&self) -> Position {
+        match self.tokens.get(self.next) {
+            Some(token) if self.scanned > self.next => token.position(),
+            _ => self.pos(),
+        }
+    }
+}
+
+/// Stream of tokens produced by a lexer with one token look-ahead and a single slot to push
+/// back an already consumed token.
+/// Comments are skipped by the stream. If comment collection is enabled the skipped comments
+/// are buffered until they are taken with `take_comments()`.
+pub struct TokenStream {
+    lexer: Box<dyn TokenSource>,
+    pushed: Option<Token>,
+    collect_comments: bool,
+    comments: Vec<Token>,
+}
+
+impl TokenStream {
+
+    pub fn new(lexer: Lexer) -> TokenStream {
+        TokenStream::with_source(Box::new(lexer))
+    }
+
+    pub fn with_source(lexer: Box<dyn TokenSource>) -> TokenStream {
+        TokenStream{ lexer, pushed: None, collect_comments: false, comments: vec![] }
+    }
+
+    /// Enables or disables buffering of skipped comments, disabled by default.
+    pub fn set_collect_comments(&mut self, collect: bool) {
+        self.collect_comments = collect;
+    }
+
+    /// Returns the comments buffered since the last call and clears the buffer.
+    pub fn take_comments(&mut self) -> Vec<Token> {
+        std::mem::take(&mut self.comments)
+    }
+
+    /// Returns the next token without consuming it. A pushed back token is returned first.
+    pub fn peek(&mut self) -> Result<Token, LexerError> {
+        if let Some(token) = &self.pushed {
+            return Ok( token.clone() )
+        }
+        self.skip_comments();
+        self.lexer.peek()
+    }
+
+    /// Returns the next token and consumes it.
+    /// Unlike `Iterator::next` this keeps returning `Token::EndOfFile` at the end of the input.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Token, LexerError> {
+        if let Some(token) = self.pushed.take() {
+            return Ok( token )
+        }
+        self.skip_comments();
+        self.lexer.get()
+    }
+
+    fn skip_comments(&mut self) {
+        while let Ok(Token::Comment{..}) | Ok(Token::DocComment{..}) = self.lexer.peek() {
+            let comment = self.lexer.get();
+            if self.collect_comments {
+                self.comments.extend(comment);
+            }
+        }
+    }
+
+    /// Consumes and returns the next token if `accept` returns true for it, otherwise the token
+    /// is left in the stream and `None` is returned.
+    pub fn expect<F: FnOnce(&Token) -> bool>(&mut self, accept: F) -> Option<Token> {
+        match self.peek() {
+            Ok(token) if accept(&token) => {
+                let _ = self.next();
+                Some(token)
+            },
+            _ => None,
+        }
+    }
+
+    /// Pushes back a token so that it is returned by the next call to `peek()` or `next()`.
+    /// # Notes
+    /// Only a single token can be pushed back, pushing back a second one before the first has been
+    /// consumed again will panic.
+    pub fn unget(&mut self, token: Token) {
+        if self.pushed.is_some() {
+            panic!("Only a single token can be pushed back into the token stream.");
+        }
+        self.pushed = Some(token);
+    }
+
+    /// Returns the current position of the underlying lexer or token source.
+    pub fn current_pos(&self) -> Position {
+        self.lexer.pos()
+    }
+
+    /// Returns the start of the next token if it has been peeked already, e.g. where a missing
+    /// token was expected, otherwise the current position.
+    pub fn next_pos(&self) -> Position {
+        match &self.pushed {
+            Some(token) => token.position(),
+            None => self.lexer.next_pos(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn stream(txt: &str) -> TokenStream {
+        TokenStream::new(Lexer::create(txt.to_string().into_bytes()))
+    }
+
+    #[test]
+    fn test_unget() {
+        let mut tokens = stream("( )");
+
+        let token = tokens.next().unwrap();
+        assert_eq!(token, Token::LeftParen(Position{ line: 1, column: 1 }));
+        assert_eq!(tokens.peek(), Ok( Token::RightParen(Position{ line: 1, column: 3 })));
+
+        tokens.unget(token);
+        assert_eq!(tokens.peek(), Ok( Token::LeftParen(Position{ line: 1, column: 1 })));
+        assert_eq!(tokens.next(), Ok( Token::LeftParen(Position{ line: 1, column: 1 })));
+        assert_eq!(tokens.next(), Ok( Token::RightParen(Position{ line: 1, column: 3 })));
+        assert!(std::matches!(tokens.next(), Ok( Token::EndOfFile(_) )));
+    }
+
+    #[test]
+    fn test_expect() {
+        let mut tokens = stream("; ,");
+
+        assert_eq!(tokens.expect(|t| std::matches!(t, Token::Comma(_))), None);
+        assert_eq!(tokens.expect(|t| std::matches!(t, Token::Semicolon(_))),
+                   Some( Token::Semicolon(Position{ line: 1, column: 1 })));
+        assert_eq!(tokens.current_pos(), Position{ line: 1, column: 1 });
+        assert_eq!(tokens.expect(|t| std::matches!(t, Token::Semicolon(_))), None);
+        assert_eq!(tokens.current_pos(), Position{ line: 1, column: 3 });
+        assert_eq!(tokens.next_pos(), Position{ line: 1, column: 3 });
+
+        let mut tokens = stream("; abc");
+        tokens.next().unwrap();
+        tokens.peek().unwrap();
+        assert_eq!(tokens.current_pos(), Position{ line: 1, column: 5 });
+        assert_eq!(tokens.next_pos(), Position{ line: 1, column: 3 });
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unget_twice() {
+        let mut tokens = stream("a b");
+        let a = tokens.next().unwrap();
+        let b = tokens.next().unwrap();
+        tokens.unget(b);
+        tokens.unget(a);
+    }
+
+    #[test]
+    fn test_token_buffer() {
+        let tokens = Lexer::create("a ;".to_string().into_bytes()).tokenize_lossy();
+        let mut tokens = TokenStream::with_source(Box::new(TokenBuffer::from(tokens)));
+
+        assert_eq!(tokens.current_pos(), Position{ line: 1, column: 0 });
+        assert_eq!(tokens.peek(), Ok( Token::Identifier{ start: Position{ line: 1, column: 1 },
+            end: Position{ line: 1, column: 1 }, source: "a".to_string() }));
+        assert_eq!(tokens.current_pos(), Position{ line: 1, column: 1 });
+        let _ = tokens.next();
+        assert_eq!(tokens.next(), Ok( Token::Semicolon(Position{ line: 1, column: 3 }) ));
+        assert_eq!(tokens.next(), Ok( Token::EndOfFile(Position{ line: 1, column: 3 }) ));
+        assert_eq!(tokens.next(), Ok( Token::EndOfFile(Position{ line: 1, column: 3 }) ));
+    }
+
+    #[test]
+    fn test_comments() {
+        let mut tokens = stream("// first\na /// second\nb // third");
+        tokens.set_collect_comments(true);
+
+        assert_eq!(tokens.next(), Ok( Token::Identifier{ start: Position{ line: 2, column: 1 },
+            end: Position{ line: 2, column: 1 }, source: "a".to_string() }));
+        assert_eq!(tokens.take_comments(), vec![
+            Token::Comment{ start: Position{ line: 1, column: 1 }, comment: " first".to_string() }]);
+        assert_eq!(tokens.peek(), Ok( Token::Identifier{ start: Position{ line: 3, column: 1 },
+            end: Position{ line: 3, column: 1 }, source: "b".to_string() }));
+        let _ = tokens.next();
+        assert!(std::matches!(tokens.next(), Ok( Token::EndOfFile(_) )));
+        assert_eq!(tokens.take_comments(), vec![
+            Token::DocComment{ start: Position{ line: 2, column: 3 }, comment: " second".to_string() },
+            Token::Comment{ start: Position{ line: 3, column: 3 }, comment: " third".to_string() }]);
+    }
+}
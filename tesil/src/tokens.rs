@@ -3,21 +3,30 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
+use std::borrow::Cow;
 use util::*;
 
 /// Type of integer base used in the source code.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum IntegerBase {
     Binary,
-    //Octal,
+    Octal,
     Decimal,
     Hexadecimal
 }
 
+/// Associativity of a binary operator, i.e. whether `a op b op c` groups as `(a op b) op c` (left)
+/// or as `a op (b op c)` (right).
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
 /// Lexemes for the TESIL language.
 #[derive(Clone, PartialEq, Debug)]
 pub enum Token {
-    EndOfFile,
+    EndOfFile(utf8::Position),      // position of the stream at the end, i.e. of the last character
     LeftParen(utf8::Position),      // '('
     RightParen(utf8::Position),     // ')'
     LeftBrace(utf8::Position),      // '{'
@@ -41,6 +50,10 @@ pub enum Token {
     Comma(utf8::Position),          // ','
     Dot(utf8::Position),            // '.'
     Hash(utf8::Position),           // '#'
+    At(utf8::Position),             // '@', reserved for pattern bindings
+    Dollar(utf8::Position),         // '$', reserved for template placeholders
+    Question(utf8::Position),       // '?'
+    Underscore(utf8::Position),     // '_' on its own, only if enabled in the lexer
 
     LessThan(utf8::Position),       // '<='
     GreaterThan(utf8::Position),    // '>='
@@ -62,9 +75,11 @@ pub enum Token {
     Unequal(utf8::Position),        // '!='
     ShiftRight(utf8::Position),     // '>>'
     ShiftLeft(utf8::Position),      // '<<'
+    ShrAssign(utf8::Position),      // '>>='
+    ShlAssign(utf8::Position),      // '<<='
 
     // Identifier string
-    // [_a-zA-Z][_a-zA-Z0-9]*
+    // [_\p{Alphabetic}][_\p{Alphabetic}\p{Numeric}]*
     Identifier {
         start: utf8::Position,
         end: utf8::Position,
@@ -77,23 +92,38 @@ pub enum Token {
         comment: String,
     },
 
+    // documentation comment /// until end of line
+    DocComment {
+        start: utf8::Position,
+        comment: String,
+    },
+
+    // region of the source that could not be scanned, only produced by `Lexer::tokenize_lossy`
+    Error {
+        pos: utf8::Position,
+        message: String,
+    },
+
     // Integer literal (unsigned)
     // Decimal: ([0-9]('[0-9])?)+
     // Binary: (0b|0B) ([01] ('[01])?)+
     // Hexadecimal: (0x|0X) ([0-9a-fA-F] ('[0-9a-fA-F])?)+
-    // Octal: <not supported>
+    // Octal: (0o|0O) ([0-7] ('[0-7])?)+
     Integer {
         start: utf8::Position,
         end: utf8::Position,
         source: String,
-        value: u64,
+        digits: String,         // source without digit separators
+        value: u128,            // range checks against the target type are left to semantic analysis
         base: IntegerBase,
+        negative: bool,         // folded negative literal, see `Parser::set_fold_unary_minus_literals`
     },
 
     FloatNumber {
         start: utf8::Position,
         end: utf8::Position,
         source: String,
+        digits: String,         // source without digit separators, exact unlike the rounded value
         value: f64,
     },
 
@@ -134,4 +164,656 @@ pub enum Token {
     KwMut(utf8::Position),          // 'mut'
     KwFalse(utf8::Position),        // 'false'
     KwTrue(utf8::Position),         // 'true'
+    KwAs(utf8::Position),           // 'as'
+    KwIf(utf8::Position),           // 'if'
+    KwElse(utf8::Position),         // 'else'
+    KwWhile(utf8::Position),        // 'while'
+    KwReturn(utf8::Position),       // 'return'
+    KwMatch(utf8::Position),        // 'match'
+    KwLoop(utf8::Position),         // 'loop'
+    KwFor(utf8::Position),          // 'for'
+    KwIn(utf8::Position),           // 'in'
+}
+
+/// Kind of a token without its position and payload, e.g. to classify or compare tokens.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum TokenKind {
+    EndOfFile,
+    LeftParen,                 // '('
+    RightParen,                // ')'
+    LeftBrace,                 // '{'
+    RightBrace,                // '}'
+    LeftBracket,               // '['
+    RightBracket,              // ']'
+    Star,                      // '*'
+    Minus,                     // '-'
+    Plus,                      // '+'
+    Slash,                     // '/'
+    Assign,                    // '='
+    Ampersand,                 // '&'
+    Vert,                      // '|'
+    Tilde,                     // '~'
+    ExclamationMark,           // '!'
+    Caret,                     // '^'
+    Less,                      // '<'
+    Greater,                   // '>'
+    Colon,                     // ':'
+    Semicolon,                 // ';'
+    Comma,                     // ','
+    Dot,                       // '.'
+    Hash,                      // '#'
+    At,                        // '@'
+    Dollar,                    // '$'
+    Question,                  // '?'
+    Underscore,                // '_'
+
+    LessThan,                  // '<='
+    GreaterThan,               // '>='
+    Implies,                   // '=>'
+    AddAssign,                 // '+='
+    SubAssign,                 // '-='
+    MulAssign,                 // '*='
+    DivAssign,                 // '/='
+    AndAssign,                 // '&='
+    OrAssign,                  // '|='
+    EXorAssign,                // '^='
+    LogicAnd,                  // '&&'
+    LogicOr,                   // '||'
+    RightArrow,                // '->'
+    LeftArrow,                 // '<-'
+    Range,                     // '..'
+    ScopeSep,                  // '::'
+    Equals,                    // '=='
+    Unequal,                   // '!='
+    ShiftRight,                // '>>'
+    ShiftLeft,                 // '<<'
+    ShrAssign,                 // '>>='
+    ShlAssign,                 // '<<='
+
+    Identifier,
+    Comment,
+    DocComment,
+    Error,
+    Integer,
+    FloatNumber,
+    String,
+    Char,
+
+    KwImport,                  // 'import'
+    KwTypeI8,                  // 'i8'
+    KwTypeI16,                 // 'i16'
+    KwTypeI32,                 // 'i32'
+    KwTypeI64,                 // 'i64'
+    KwTypeU8,                  // 'u8'
+    KwTypeU16,                 // 'u16'
+    KwTypeU32,                 // 'u32'
+    KwTypeU64,                 // 'u64'
+    KwTypeBool,                // 'bool'
+    KwTypeF32,                 // 'f32'
+    KwTypeF64,                 // 'f64'
+    KwTypeChar,                // 'char'
+    KwFn,                      // 'fn'
+    KwStruct,                  // 'struct'
+    KwEnum,                    // 'enum'
+    KwType,                    // 'type'
+    KwBreak,                   // 'break'
+    KwContinue,                // 'continue'
+    KwExpect,                  // 'expect'
+    KwLet,                     // 'let'
+    KwMut,                     // 'mut'
+    KwFalse,                   // 'false'
+    KwTrue,                    // 'true'
+    KwAs,                      // 'as'
+    KwIf,                      // 'if'
+    KwElse,                    // 'else'
+    KwWhile,                   // 'while'
+    KwReturn,                  // 'return'
+    KwMatch,                   // 'match'
+    KwLoop,                    // 'loop'
+    KwFor,                     // 'for'
+    KwIn,                      // 'in'
+}
+
+impl Token {
+    /// Returns the text of the token as it would be written in the source code.
+    /// Punctuation and keywords return their canonical spelling, identifiers and numbers the
+    /// scanned source text and strings or characters a quoted and escaped form.
+    pub fn spelling(&self) -> Cow<'_, str> {
+        match self {
+            Token::EndOfFile(_)             => Cow::Borrowed(""),
+            Token::LeftParen(_)             => Cow::Borrowed("("),
+            Token::RightParen(_)            => Cow::Borrowed(")"),
+            Token::LeftBrace(_)             => Cow::Borrowed("{"),
+            Token::RightBrace(_)            => Cow::Borrowed("}"),
+            Token::LeftBracket(_)           => Cow::Borrowed("["),
+            Token::RightBracket(_)          => Cow::Borrowed("]"),
+            Token::Star(_)                  => Cow::Borrowed("*"),
+            Token::Minus(_)                 => Cow::Borrowed("-"),
+            Token::Plus(_)                  => Cow::Borrowed("+"),
+            Token::Slash(_)                 => Cow::Borrowed("/"),
+            Token::Assign(_)                => Cow::Borrowed("="),
+            Token::Ampersand(_)             => Cow::Borrowed("&"),
+            Token::Vert(_)                  => Cow::Borrowed("|"),
+            Token::Tilde(_)                 => Cow::Borrowed("~"),
+            Token::ExclamationMark(_)       => Cow::Borrowed("!"),
+            Token::Caret(_)                 => Cow::Borrowed("^"),
+            Token::Less(_)                  => Cow::Borrowed("<"),
+            Token::Greater(_)               => Cow::Borrowed(">"),
+            Token::Colon(_)                 => Cow::Borrowed(":"),
+            Token::Semicolon(_)             => Cow::Borrowed(";"),
+            Token::Comma(_)                 => Cow::Borrowed(","),
+            Token::Dot(_)                   => Cow::Borrowed("."),
+            Token::Hash(_)                  => Cow::Borrowed("#"),
+            Token::At(_)                    => Cow::Borrowed("@"),
+            Token::Dollar(_)                => Cow::Borrowed("$"),
+            Token::Question(_)              => Cow::Borrowed("?"),
+            Token::Underscore(_)            => Cow::Borrowed("_"),
+            Token::LessThan(_)              => Cow::Borrowed("<="),
+            Token::GreaterThan(_)           => Cow::Borrowed(">="),
+            Token::Implies(_)               => Cow::Borrowed("=>"),
+            Token::AddAssign(_)             => Cow::Borrowed("+="),
+            Token::SubAssign(_)             => Cow::Borrowed("-="),
+            Token::MulAssign(_)             => Cow::Borrowed("*="),
+            Token::DivAssign(_)             => Cow::Borrowed("/="),
+            Token::AndAssign(_)             => Cow::Borrowed("&="),
+            Token::OrAssign(_)              => Cow::Borrowed("|="),
+            Token::EXorAssign(_)            => Cow::Borrowed("^="),
+            Token::LogicAnd(_)              => Cow::Borrowed("&&"),
+            Token::LogicOr(_)               => Cow::Borrowed("||"),
+            Token::RightArrow(_)            => Cow::Borrowed("->"),
+            Token::LeftArrow(_)             => Cow::Borrowed("<-"),
+            Token::Range(_)                 => Cow::Borrowed(".."),
+            Token::ScopeSep(_)              => Cow::Borrowed("::"),
+            Token::Equals(_)                => Cow::Borrowed("=="),
+            Token::Unequal(_)               => Cow::Borrowed("!="),
+            Token::ShiftRight(_)            => Cow::Borrowed(">>"),
+            Token::ShiftLeft(_)             => Cow::Borrowed("<<"),
+            Token::ShrAssign(_)             => Cow::Borrowed(">>="),
+            Token::ShlAssign(_)             => Cow::Borrowed("<<="),
+            Token::KwImport(_)              => Cow::Borrowed("import"),
+            Token::KwTypeI8(_)              => Cow::Borrowed("i8"),
+            Token::KwTypeI16(_)             => Cow::Borrowed("i16"),
+            Token::KwTypeI32(_)             => Cow::Borrowed("i32"),
+            Token::KwTypeI64(_)             => Cow::Borrowed("i64"),
+            Token::KwTypeU8(_)              => Cow::Borrowed("u8"),
+            Token::KwTypeU16(_)             => Cow::Borrowed("u16"),
+            Token::KwTypeU32(_)             => Cow::Borrowed("u32"),
+            Token::KwTypeU64(_)             => Cow::Borrowed("u64"),
+            Token::KwTypeBool(_)            => Cow::Borrowed("bool"),
+            Token::KwTypeF32(_)             => Cow::Borrowed("f32"),
+            Token::KwTypeF64(_)             => Cow::Borrowed("f64"),
+            Token::KwTypeChar(_)            => Cow::Borrowed("char"),
+            Token::KwFn(_)                  => Cow::Borrowed("fn"),
+            Token::KwStruct(_)              => Cow::Borrowed("struct"),
+            Token::KwEnum(_)                => Cow::Borrowed("enum"),
+            Token::KwType(_)                => Cow::Borrowed("type"),
+            Token::KwBreak(_)               => Cow::Borrowed("break"),
+            Token::KwContinue(_)            => Cow::Borrowed("continue"),
+            Token::KwExpect(_)              => Cow::Borrowed("expect"),
+            Token::KwLet(_)                 => Cow::Borrowed("let"),
+            Token::KwMut(_)                 => Cow::Borrowed("mut"),
+            Token::KwFalse(_)               => Cow::Borrowed("false"),
+            Token::KwTrue(_)                => Cow::Borrowed("true"),
+            Token::KwAs(_)                  => Cow::Borrowed("as"),
+            Token::KwIf(_)                  => Cow::Borrowed("if"),
+            Token::KwElse(_)                => Cow::Borrowed("else"),
+            Token::KwWhile(_)               => Cow::Borrowed("while"),
+            Token::KwReturn(_)              => Cow::Borrowed("return"),
+            Token::KwMatch(_)               => Cow::Borrowed("match"),
+            Token::KwLoop(_)                => Cow::Borrowed("loop"),
+            Token::KwFor(_)                 => Cow::Borrowed("for"),
+            Token::KwIn(_)                  => Cow::Borrowed("in"),
+            Token::Identifier{source, ..}   => Cow::Borrowed(source),
+            Token::Integer{source, ..}      => Cow::Borrowed(source),
+            Token::FloatNumber{source, ..}  => Cow::Borrowed(source),
+            Token::Comment{comment, ..}     => Cow::Owned(format!("//{}", comment)),
+            Token::DocComment{comment, ..}  => Cow::Owned(format!("///{}", comment)),
+            Token::Error{..}                => Cow::Borrowed(""),
+            Token::String{source, ..}       =>
+                Cow::Owned(format!("\"{}\"", source.chars().map(|c| escape(c, '"')).collect::<String>())),
+            Token::Char{ch, ..}             => Cow::Owned(format!("'{}'", escape(*ch, '\''))),
+        }
+    }
+    /// Returns the position of the first character of the token.
+    pub fn position(&self) -> utf8::Position {
+        match self {
+            Token::EndOfFile(pos)
+            | Token::LeftParen(pos)
+            | Token::RightParen(pos)
+            | Token::LeftBrace(pos)
+            | Token::RightBrace(pos)
+            | Token::LeftBracket(pos)
+            | Token::RightBracket(pos)
+            | Token::Star(pos)
+            | Token::Minus(pos)
+            | Token::Plus(pos)
+            | Token::Slash(pos)
+            | Token::Assign(pos)
+            | Token::Ampersand(pos)
+            | Token::Vert(pos)
+            | Token::Tilde(pos)
+            | Token::ExclamationMark(pos)
+            | Token::Caret(pos)
+            | Token::Less(pos)
+            | Token::Greater(pos)
+            | Token::Colon(pos)
+            | Token::Semicolon(pos)
+            | Token::Comma(pos)
+            | Token::Dot(pos)
+            | Token::Hash(pos)
+            | Token::At(pos)
+            | Token::Dollar(pos)
+            | Token::Question(pos)
+            | Token::Underscore(pos)
+            | Token::LessThan(pos)
+            | Token::GreaterThan(pos)
+            | Token::Implies(pos)
+            | Token::AddAssign(pos)
+            | Token::SubAssign(pos)
+            | Token::MulAssign(pos)
+            | Token::DivAssign(pos)
+            | Token::AndAssign(pos)
+            | Token::OrAssign(pos)
+            | Token::EXorAssign(pos)
+            | Token::LogicAnd(pos)
+            | Token::LogicOr(pos)
+            | Token::RightArrow(pos)
+            | Token::LeftArrow(pos)
+            | Token::Range(pos)
+            | Token::ScopeSep(pos)
+            | Token::Equals(pos)
+            | Token::Unequal(pos)
+            | Token::ShiftRight(pos)
+            | Token::ShiftLeft(pos)
+            | Token::ShrAssign(pos)
+            | Token::ShlAssign(pos)
+            | Token::KwImport(pos)
+            | Token::KwTypeI8(pos)
+            | Token::KwTypeI16(pos)
+            | Token::KwTypeI32(pos)
+            | Token::KwTypeI64(pos)
+            | Token::KwTypeU8(pos)
+            | Token::KwTypeU16(pos)
+            | Token::KwTypeU32(pos)
+            | Token::KwTypeU64(pos)
+            | Token::KwTypeBool(pos)
+            | Token::KwTypeF32(pos)
+            | Token::KwTypeF64(pos)
+            | Token::KwTypeChar(pos)
+            | Token::KwFn(pos)
+            | Token::KwStruct(pos)
+            | Token::KwEnum(pos)
+            | Token::KwType(pos)
+            | Token::KwBreak(pos)
+            | Token::KwContinue(pos)
+            | Token::KwExpect(pos)
+            | Token::KwLet(pos)
+            | Token::KwMut(pos)
+            | Token::KwFalse(pos)
+            | Token::KwTrue(pos)
+            | Token::KwAs(pos)
+            | Token::KwIf(pos)
+            | Token::KwElse(pos)
+            | Token::KwWhile(pos)
+            | Token::KwReturn(pos)
+            | Token::KwMatch(pos)
+            | Token::KwLoop(pos)
+            | Token::KwFor(pos)
+            | Token::KwIn(pos) => *pos,
+            Token::Identifier{start, ..}
+            | Token::Comment{start, ..}
+            | Token::DocComment{start, ..}
+            | Token::Error{pos: start, ..}
+            | Token::Integer{start, ..}
+            | Token::FloatNumber{start, ..}
+            | Token::String{start, ..}
+            | Token::Char{start, ..} => *start,
+        }
+    }
+
+    /// Returns the position of the last character of the token, for the end of file its position.
+    /// Tokens storing only their start position are assumed to be spelled as given by `spelling()`.
+    pub fn end_position(&self) -> utf8::Position {
+        match self {
+            Token::Identifier{end, ..}
+            | Token::Integer{end, ..}
+            | Token::FloatNumber{end, ..}
+            | Token::String{end, ..} => *end,
+            _ => self.position().advanced(self.spelling().chars().count().saturating_sub(1) as u32),
+        }
+    }
+    /// Compares two tokens by kind and payload (source text, values, integer base) but ignores
+    /// their positions, useful to write tests without spelling out every position.
+    pub fn eq_ignoring_pos(&self, other: &Token) -> bool {
+        match (self, other) {
+            (Token::Identifier{source: a, ..}, Token::Identifier{source: b, ..}) => a == b,
+            (Token::Comment{comment: a, ..}, Token::Comment{comment: b, ..}) => a == b,
+            (Token::DocComment{comment: a, ..}, Token::DocComment{comment: b, ..}) => a == b,
+            (Token::Error{message: a, ..}, Token::Error{message: b, ..}) => a == b,
+            (Token::Integer{source: a, value: va, base: ba, negative: na, ..},
+             Token::Integer{source: b, value: vb, base: bb, negative: nb, ..}) => a == b && va == vb && ba == bb && na == nb,
+            (Token::FloatNumber{source: a, value: va, ..}, Token::FloatNumber{source: b, value: vb, ..}) =>
+                a == b && va == vb,
+            (Token::String{source: a, ..}, Token::String{source: b, ..}) => a == b,
+            (Token::Char{ch: a, ..}, Token::Char{ch: b, ..}) => a == b,
+            // all remaining variants carry at most a position
+            _ => self.kind() == other.kind(),
+        }
+    }
+
+    /// Returns the kind of the token, tokens of the same kind differ only in position and payload.
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::EndOfFile(_)             => TokenKind::EndOfFile,
+            Token::LeftParen(_)             => TokenKind::LeftParen,
+            Token::RightParen(_)            => TokenKind::RightParen,
+            Token::LeftBrace(_)             => TokenKind::LeftBrace,
+            Token::RightBrace(_)            => TokenKind::RightBrace,
+            Token::LeftBracket(_)           => TokenKind::LeftBracket,
+            Token::RightBracket(_)          => TokenKind::RightBracket,
+            Token::Star(_)                  => TokenKind::Star,
+            Token::Minus(_)                 => TokenKind::Minus,
+            Token::Plus(_)                  => TokenKind::Plus,
+            Token::Slash(_)                 => TokenKind::Slash,
+            Token::Assign(_)                => TokenKind::Assign,
+            Token::Ampersand(_)             => TokenKind::Ampersand,
+            Token::Vert(_)                  => TokenKind::Vert,
+            Token::Tilde(_)                 => TokenKind::Tilde,
+            Token::ExclamationMark(_)       => TokenKind::ExclamationMark,
+            Token::Caret(_)                 => TokenKind::Caret,
+            Token::Less(_)                  => TokenKind::Less,
+            Token::Greater(_)               => TokenKind::Greater,
+            Token::Colon(_)                 => TokenKind::Colon,
+            Token::Semicolon(_)             => TokenKind::Semicolon,
+            Token::Comma(_)                 => TokenKind::Comma,
+            Token::Dot(_)                   => TokenKind::Dot,
+            Token::Hash(_)                  => TokenKind::Hash,
+            Token::At(_)                    => TokenKind::At,
+            Token::Dollar(_)                => TokenKind::Dollar,
+            Token::Question(_)              => TokenKind::Question,
+            Token::Underscore(_)            => TokenKind::Underscore,
+            Token::LessThan(_)              => TokenKind::LessThan,
+            Token::GreaterThan(_)           => TokenKind::GreaterThan,
+            Token::Implies(_)               => TokenKind::Implies,
+            Token::AddAssign(_)             => TokenKind::AddAssign,
+            Token::SubAssign(_)             => TokenKind::SubAssign,
+            Token::MulAssign(_)             => TokenKind::MulAssign,
+            Token::DivAssign(_)             => TokenKind::DivAssign,
+            Token::AndAssign(_)             => TokenKind::AndAssign,
+            Token::OrAssign(_)              => TokenKind::OrAssign,
+            Token::EXorAssign(_)            => TokenKind::EXorAssign,
+            Token::LogicAnd(_)              => TokenKind::LogicAnd,
+            Token::LogicOr(_)               => TokenKind::LogicOr,
+            Token::RightArrow(_)            => TokenKind::RightArrow,
+            Token::LeftArrow(_)             => TokenKind::LeftArrow,
+            Token::Range(_)                 => TokenKind::Range,
+            Token::ScopeSep(_)              => TokenKind::ScopeSep,
+            Token::Equals(_)                => TokenKind::Equals,
+            Token::Unequal(_)               => TokenKind::Unequal,
+            Token::ShiftRight(_)            => TokenKind::ShiftRight,
+            Token::ShiftLeft(_)             => TokenKind::ShiftLeft,
+            Token::ShrAssign(_)             => TokenKind::ShrAssign,
+            Token::ShlAssign(_)             => TokenKind::ShlAssign,
+            Token::Identifier{..}           => TokenKind::Identifier,
+            Token::Comment{..}              => TokenKind::Comment,
+            Token::DocComment{..}           => TokenKind::DocComment,
+            Token::Error{..}                => TokenKind::Error,
+            Token::Integer{..}              => TokenKind::Integer,
+            Token::FloatNumber{..}          => TokenKind::FloatNumber,
+            Token::String{..}               => TokenKind::String,
+            Token::Char{..}                 => TokenKind::Char,
+            Token::KwImport(_)              => TokenKind::KwImport,
+            Token::KwTypeI8(_)              => TokenKind::KwTypeI8,
+            Token::KwTypeI16(_)             => TokenKind::KwTypeI16,
+            Token::KwTypeI32(_)             => TokenKind::KwTypeI32,
+            Token::KwTypeI64(_)             => TokenKind::KwTypeI64,
+            Token::KwTypeU8(_)              => TokenKind::KwTypeU8,
+            Token::KwTypeU16(_)             => TokenKind::KwTypeU16,
+            Token::KwTypeU32(_)             => TokenKind::KwTypeU32,
+            Token::KwTypeU64(_)             => TokenKind::KwTypeU64,
+            Token::KwTypeBool(_)            => TokenKind::KwTypeBool,
+            Token::KwTypeF32(_)             => TokenKind::KwTypeF32,
+            Token::KwTypeF64(_)             => TokenKind::KwTypeF64,
+            Token::KwTypeChar(_)            => TokenKind::KwTypeChar,
+            Token::KwFn(_)                  => TokenKind::KwFn,
+            Token::KwStruct(_)              => TokenKind::KwStruct,
+            Token::KwEnum(_)                => TokenKind::KwEnum,
+            Token::KwType(_)                => TokenKind::KwType,
+            Token::KwBreak(_)               => TokenKind::KwBreak,
+            Token::KwContinue(_)            => TokenKind::KwContinue,
+            Token::KwExpect(_)              => TokenKind::KwExpect,
+            Token::KwLet(_)                 => TokenKind::KwLet,
+            Token::KwMut(_)                 => TokenKind::KwMut,
+            Token::KwFalse(_)               => TokenKind::KwFalse,
+            Token::KwTrue(_)                => TokenKind::KwTrue,
+            Token::KwAs(_)                  => TokenKind::KwAs,
+            Token::KwIf(_)                  => TokenKind::KwIf,
+            Token::KwElse(_)                => TokenKind::KwElse,
+            Token::KwWhile(_)               => TokenKind::KwWhile,
+            Token::KwReturn(_)              => TokenKind::KwReturn,
+            Token::KwMatch(_)               => TokenKind::KwMatch,
+            Token::KwLoop(_)                => TokenKind::KwLoop,
+            Token::KwFor(_)                 => TokenKind::KwFor,
+            Token::KwIn(_)                  => TokenKind::KwIn,
+        }
+    }
+
+    /// Returns whether the token opens a parenthesis, brace or bracket.
+    pub fn is_open(&self) -> bool {
+        self.closing_delimiter().is_some()
+    }
+
+    /// Returns whether the token closes a parenthesis, brace or bracket.
+    pub fn is_close(&self) -> bool {
+        self.opening_delimiter().is_some()
+    }
+
+    /// Returns the kind of the delimiter closed by the token, `None` for other tokens.
+    pub fn opening_delimiter(&self) -> Option<TokenKind> {
+        match self {
+            Token::RightParen(_)    => Some(TokenKind::LeftParen),
+            Token::RightBrace(_)    => Some(TokenKind::LeftBrace),
+            Token::RightBracket(_)  => Some(TokenKind::LeftBracket),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of the delimiter closing the one opened by the token, `None` for other
+    /// tokens.
+    pub fn closing_delimiter(&self) -> Option<TokenKind> {
+        match self {
+            Token::LeftParen(_)     => Some(TokenKind::RightParen),
+            Token::LeftBrace(_)     => Some(TokenKind::RightBrace),
+            Token::LeftBracket(_)   => Some(TokenKind::RightBracket),
+            _ => None,
+        }
+    }
+
+    /// Returns the precedence level and associativity of the binary operators of the expression
+    /// grammar, operators of a higher level bind tighter. `None` for other tokens.
+    pub fn binary_precedence(&self) -> Option<(u8, Associativity)> {
+        match self {
+            Token::Assign(_) | Token::AddAssign(_) | Token::SubAssign(_) | Token::MulAssign(_)
+            | Token::DivAssign(_) | Token::AndAssign(_) | Token::OrAssign(_) | Token::EXorAssign(_)
+            | Token::ShlAssign(_) | Token::ShrAssign(_)             => Some((1, Associativity::Right)),
+            Token::Range(_)                                         => Some((2, Associativity::Left)),
+            Token::Equals(_) | Token::Unequal(_)                    => Some((3, Associativity::Left)),
+            Token::Greater(_) | Token::GreaterThan(_) | Token::Less(_)
+            | Token::LessThan(_)                                    => Some((4, Associativity::Left)),
+            Token::Plus(_) | Token::Minus(_)                        => Some((5, Associativity::Left)),
+            Token::Star(_) | Token::Slash(_)                        => Some((6, Associativity::Left)),
+            _ => None,
+        }
+    }
+
+    /// Returns the digits of an integer literal without base prefix and digit separators, e.g. `FF`
+    /// for `0xFF`, keeping the sign of a folded negative literal. `None` for other tokens.
+    pub fn canonical_digits(&self) -> Option<String> {
+        match self {
+            Token::Integer{digits, base, negative, ..} => {
+                let sign = if *negative { "-" } else { "" };
+                let unsigned = digits.strip_prefix('-').unwrap_or(digits);
+                let prefix = match base {
+                    IntegerBase::Decimal        => "",
+                    IntegerBase::Hexadecimal    => "0x",
+                    IntegerBase::Binary         => "0b",
+                    IntegerBase::Octal          => "0o",
+                };
+                let unprefixed = match unsigned.get(..prefix.len()) {
+                    Some(start) if start.eq_ignore_ascii_case(prefix) => &unsigned[prefix.len()..],
+                    _ => unsigned,
+                };
+                Some( format!("{}{}", sign, unprefixed) )
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns whether the token is a reserved word, including the literals `true` and `false`.
+    pub fn is_keyword(&self) -> bool {
+        std::matches!(self, Token::KwImport(_) | Token::KwTypeI8(_) | Token::KwTypeI16(_)
+            | Token::KwTypeI32(_) | Token::KwTypeI64(_) | Token::KwTypeU8(_) | Token::KwTypeU16(_)
+            | Token::KwTypeU32(_) | Token::KwTypeU64(_) | Token::KwTypeBool(_) | Token::KwTypeF32(_)
+            | Token::KwTypeF64(_) | Token::KwTypeChar(_) | Token::KwFn(_) | Token::KwStruct(_)
+            | Token::KwEnum(_) | Token::KwType(_) | Token::KwBreak(_) | Token::KwContinue(_)
+            | Token::KwExpect(_) | Token::KwLet(_) | Token::KwMut(_) | Token::KwFalse(_)
+            | Token::KwTrue(_) | Token::KwAs(_) | Token::KwIf(_) | Token::KwElse(_) | Token::KwWhile(_)
+            | Token::KwReturn(_) | Token::KwMatch(_) | Token::KwLoop(_) | Token::KwFor(_) | Token::KwIn(_))
+    }
+}
+
+/// Escapes a character within a string or character literal delimited by `quote`.
+fn escape(ch: char, quote: char) -> String {
+    match ch {
+        '\n' => "\\n".to_string(),
+        '\t' => "\\t".to_string(),
+        '\r' => "\\r".to_string(),
+        '\\' => "\\\\".to_string(),
+        c if c == quote => format!("\\{}", c),
+        c if c.is_control() => format!("\\u{{{:04x}}}", c as u32),
+        c => c.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use util::utf8::Position;
+
+    fn position() -> Position {
+        Position{ line: 1, column: 1 }
+    }
+
+    #[test]
+    fn test_spelling() {
+        assert_eq!(Token::KwFn(position()).spelling(), "fn");
+        assert_eq!(Token::KwTypeChar(position()).spelling(), "char");
+        assert_eq!(Token::AddAssign(position()).spelling(), "+=");
+        assert_eq!(Token::ShrAssign(position()).spelling(), ">>=");
+        assert_eq!(Token::ScopeSep(position()).spelling(), "::");
+        assert_eq!(Token::EndOfFile(position()).spelling(), "");
+        assert_eq!(Token::Identifier{ start: position(), end: position(), source: "abc".to_string() }
+            .spelling(), "abc");
+        assert_eq!(Token::Integer{ start: position(), end: position(), source: "0x1'F".to_string(), digits: "0x1F".to_string(), value: 31,
+            base: IntegerBase::Hexadecimal, negative: false }.spelling(), "0x1'F");
+        assert_eq!(Token::FloatNumber{ start: position(), end: position(), source: "1.5e3".to_string(), digits: "1.5e3".to_string(),
+            value: 1.5e3 }.spelling(), "1.5e3");
+        assert_eq!(Token::String{ start: position(), end: position(), source: "a\"b\n".to_string() }
+            .spelling(), "\"a\\\"b\\n\"");
+        assert_eq!(Token::Char{ start: position(), ch: '\'' }.spelling(), "'\\''");
+        assert_eq!(Token::Char{ start: position(), ch: '\u{0001}' }.spelling(), "'\\u{0001}'");
+        assert_eq!(Token::DocComment{ start: position(), comment: " doc".to_string() }.spelling(), "/// doc");
+    }
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(Token::EndOfFile(position()).kind(), TokenKind::EndOfFile);
+        assert_eq!(Token::ShlAssign(position()).kind(), TokenKind::ShlAssign);
+        assert_eq!(Token::KwWhile(position()).kind(), TokenKind::KwWhile);
+        assert_eq!(Token::Char{ start: position(), ch: 'x' }.kind(), TokenKind::Char);
+
+        let a = Token::Identifier{ start: position(), end: position(), source: "a".to_string() };
+        let b = Token::Identifier{ start: Position{ line: 3, column: 2 }, end: Position{ line: 3, column: 4 },
+            source: "bcd".to_string() };
+        assert_eq!(a.kind(), b.kind());
+        assert_eq!(Token::Plus(position()).kind(), Token::Plus(Position{ line: 9, column: 9 }).kind());
+        assert_ne!(Token::Plus(position()).kind(), Token::Minus(position()).kind());
+    }
+
+    #[test]
+    fn test_delimiters() {
+        let pairs = [
+            (Token::LeftParen(position()), Token::RightParen(position())),
+            (Token::LeftBrace(position()), Token::RightBrace(position())),
+            (Token::LeftBracket(position()), Token::RightBracket(position())),
+        ];
+        for (open, close) in pairs {
+            assert!(open.is_open() && !open.is_close());
+            assert!(close.is_close() && !close.is_open());
+            assert_eq!(open.closing_delimiter(), Some(close.kind()));
+            assert_eq!(close.opening_delimiter(), Some(open.kind()));
+            assert_eq!(open.opening_delimiter(), None);
+            assert_eq!(close.closing_delimiter(), None);
+        }
+        assert!(!Token::Less(position()).is_open() && !Token::Greater(position()).is_close());
+        assert_eq!(Token::EndOfFile(position()).closing_delimiter(), None);
+    }
+
+    #[test]
+    fn test_binary_precedence() {
+        assert_eq!(Token::Assign(position()).binary_precedence(), Some((1, Associativity::Right)));
+        assert_eq!(Token::ShrAssign(position()).binary_precedence(), Some((1, Associativity::Right)));
+        assert_eq!(Token::Range(position()).binary_precedence(), Some((2, Associativity::Left)));
+        assert_eq!(Token::Unequal(position()).binary_precedence(), Some((3, Associativity::Left)));
+        assert_eq!(Token::LessThan(position()).binary_precedence(), Some((4, Associativity::Left)));
+        assert_eq!(Token::Minus(position()).binary_precedence(), Some((5, Associativity::Left)));
+        assert_eq!(Token::Slash(position()).binary_precedence(), Some((6, Associativity::Left)));
+        assert_eq!(Token::KwAs(position()).binary_precedence(), None);
+        assert_eq!(Token::EndOfFile(position()).binary_precedence(), None);
+    }
+
+    #[test]
+    fn test_eq_ignoring_pos() {
+        let a = Token::Integer{ start: position(), end: position(), source: "42".to_string(), digits: "42".to_string(), value: 42,
+            base: IntegerBase::Decimal, negative: false };
+        let b = Token::Integer{ start: Position{ line: 7, column: 3 }, end: Position{ line: 7, column: 4 },
+            source: "42".to_string(), digits: "42".to_string(), value: 42, base: IntegerBase::Decimal, negative: false };
+        let c = Token::Integer{ start: position(), end: position(), source: "0x2A".to_string(), digits: "0x2A".to_string(), value: 42,
+            base: IntegerBase::Hexadecimal, negative: false };
+        assert_ne!(a, b);
+        assert!(a.eq_ignoring_pos(&b));
+        assert!(!a.eq_ignoring_pos(&c));
+        assert!(Token::Comma(position()).eq_ignoring_pos(&Token::Comma(Position{ line: 2, column: 9 })));
+        assert!(!Token::Comma(position()).eq_ignoring_pos(&Token::Semicolon(position())));
+        assert!(Token::EndOfFile(position()).eq_ignoring_pos(&Token::EndOfFile(Position{ line: 2, column: 9 })));
+    }
+
+    #[test]
+    fn test_canonical_digits() {
+        let digits = |txt: &str| crate::Lexer::create(txt.to_string().into_bytes()).get().unwrap().canonical_digits();
+        assert_eq!(digits("0xFF"), Some("FF".to_string()));
+        assert_eq!(digits("0XfF"), Some("fF".to_string()));
+        assert_eq!(digits("0b1010"), Some("1010".to_string()));
+        assert_eq!(digits("0o7'7"), Some("77".to_string()));
+        assert_eq!(digits("1'000"), Some("1000".to_string()));
+        assert_eq!(digits("1.5"), None);
+        assert_eq!(digits("abc"), None);
+
+        let negative = Token::Integer{ start: position(), end: position(), source: "-0x1'0".to_string(),
+            digits: "-0x10".to_string(), value: 16, base: IntegerBase::Hexadecimal, negative: true };
+        assert_eq!(negative.canonical_digits(), Some("-10".to_string()));
+    }
+
+    #[test]
+    fn test_end_position() {
+        let start = Position{ line: 2, column: 3 };
+        assert_eq!(Token::ScopeSep(start).position(), start);
+        assert_eq!(Token::ScopeSep(start).end_position(), Position{ line: 2, column: 4 });
+        assert_eq!(Token::Semicolon(start).end_position(), start);
+        assert_eq!(Token::KwReturn(start).end_position(), Position{ line: 2, column: 8 });
+        assert_eq!(Token::Char{ start, ch: 'x' }.end_position(), Position{ line: 2, column: 5 });
+        assert_eq!(Token::Identifier{ start, end: Position{ line: 2, column: 6 }, source: "abcd".to_string() }
+            .end_position(), Position{ line: 2, column: 6 });
+        assert_eq!(Token::EndOfFile(start).end_position(), start);
+    }
 }
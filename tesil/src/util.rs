@@ -3,71 +3,70 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
-use super::ast::Expression;
+use std::fmt;
+use super::ast::{Expression, Pattern, Statement, TypeRef};
 use super::tokens;
 
-trait AstVisitor<T> {
+pub mod balance;
+pub mod diagnostics;
+pub mod fuzz;
+pub mod idents;
+pub mod ron;
+pub mod spans;
+
+pub trait AstVisitor<T> {
     fn visit_expression(&mut self, expr: &Expression) -> T;
+    fn visit_statement(&mut self, stmt: &Statement) -> T;
 }
 
-struct Ast2Json {
-    intent_stack: Vec<String>
+/// Serializes the AST into pretty-printed JSON, indenting every nesting level by two spaces.
+pub struct Ast2Json {
+    depth: usize,
 }
 
-impl AstVisitor<String> for Ast2Json {
-
-    fn visit_expression(&mut self, expr: &Expression) -> String {
-        use super::ast::Expression::*;
-        match expr {
-            Literal(token) => self.visit_literal(token),
-            Binary{lhs, operator, rhs} => self.visit_expr_binary(lhs, operator, rhs),
-            Unary{operator, rhs} => self.visit_expr_unary(rhs, operator),
-            //_ => "".to_string()
-        }
+impl Default for Ast2Json {
+    fn default() -> Self {
+        Ast2Json::new()
     }
 }
 
-impl Ast2Json {
+impl AstVisitor<String> for Ast2Json {
 
-    pub fn new() -> Ast2Json {
-        Ast2Json{ intent_stack: vec!["".to_string()]}
+    fn visit_expression(&mut self, expr: &Expression) -> String {
+        let mut out = String::new();
+        // writing into a string only fails for a malformed tree, whose output ends at the bad node
+        let _ = JsonWriter{ out: &mut out }.expression(expr, self.depth);
+        out
     }
 
-    fn visit_expr_binary(&mut self, lhs: &Expression, op: &tokens::Token, rhs: &Expression) -> String {
-        self.intent_stack.push(self.intent_stack.last().unwrap().clone() + "    ");
-        let lhs_str = self.visit_expression(lhs);
-        let rhs_str = self.visit_expression(rhs);
-        self.intent_stack.pop();
-
-        let intent = self.intent_stack.last().unwrap().clone() + "  ";
-        format!("{{\n{}expression: binary,\n{}operator: {},\n{}lhs: {},\n{}rhs: {}\n{}}}",
-            intent, intent, Ast2Json::operator_val(op),
-            intent, lhs_str, intent, rhs_str, self.intent_stack.last().unwrap())
+    fn visit_statement(&mut self, stmt: &Statement) -> String {
+        let mut out = String::new();
+        let _ = JsonWriter{ out: &mut out }.statement(stmt, self.depth);
+        out
     }
+}
 
-    fn visit_expr_unary(&mut self, rhs: &Expression, op: &tokens::Token) -> String {
-        self.intent_stack.push(self.intent_stack.last().unwrap().clone() + "    ");
-        let rhs_str = self.visit_expression(rhs);
-        self.intent_stack.pop();
+/// Writes the expression as pretty-printed JSON into `out`, e.g. a `String` or a formatter,
+/// see `Ast2Json`.
+pub fn to_json<W: fmt::Write>(expr: &Expression, out: &mut W) -> fmt::Result {
+    Ast2Json::new().write_json(expr, out)
+}
+
+impl Ast2Json {
 
-        let intent = self.intent_stack.last().unwrap().clone() + "  ";
-        format!("{{\n{}expression: unary,\n{}operator: {},\n{}rhs: {}\n{}}}",
-                intent, intent, Ast2Json::operator_val(op),
-                intent, rhs_str, self.intent_stack.last().unwrap())
+    pub fn new() -> Ast2Json {
+        Ast2Json{ depth: 0 }
     }
 
-    fn visit_literal(&mut self, token: &tokens::Token) -> String {
-        match token {
-            tokens::Token::Integer {value, base, source, ..} => {
-                format!("{{type: integer, base: {}, literal: {}, value: {} }}",
-                        Ast2Json::integer_base_value(base), source, value).to_string()
-            },
-            _ => "".to_string(),
-        }
+    /// Writes the expression into `out` without building the JSON text in memory first.
+    /// A malformed tree, e.g. built by hand with a non-identifier token as name, is reported as
+    /// `fmt::Error`.
+    pub fn write_json<W: fmt::Write>(&mut self, expr: &Expression, out: &mut W) -> fmt::Result {
+        JsonWriter{ out }.expression(expr, self.depth)
     }
 
-    fn operator_val(token: &tokens::Token) -> &str{
-        match token {
+    fn operator_val(token: &tokens::Token) -> Result<&str, fmt::Error> {
+        Ok( match token {
             tokens::Token::Plus(_)      => "+",
             tokens::Token::Minus(_)     => "-",
             tokens::Token::Star(_)      => "*",
@@ -84,14 +83,47 @@ impl Ast2Json {
             tokens::Token::LogicAnd(_)  => "&&",
             tokens::Token::Equals(_)    => "==",
             tokens::Token::Unequal(_)   => "!=",
+            tokens::Token::ShiftLeft(_) => "<<",
+            tokens::Token::ShiftRight(_) => ">>",
             tokens::Token::AddAssign(_) => "+=",
             tokens::Token::SubAssign(_) => "-=",
             tokens::Token::MulAssign(_) => "*=",
             tokens::Token::DivAssign(_) => "/=",
             tokens::Token::OrAssign(_)  => "|=",
             tokens::Token::AndAssign(_) => "&=",
-            _ => panic!("Unsupported token for an operator"),
-         }
+            tokens::Token::EXorAssign(_) => "^=",
+            tokens::Token::ShlAssign(_) => "<<=",
+            tokens::Token::ShrAssign(_) => ">>=",
+            tokens::Token::Assign(_)    => "=",
+            tokens::Token::Range(_)     => "..",
+            _ => return Err(fmt::Error),
+         })
+    }
+
+    fn identifier_val(token: &tokens::Token) -> Result<&str, fmt::Error> {
+        match token {
+            tokens::Token::Identifier {source, ..} => Ok(source),
+            _ => Err(fmt::Error),
+        }
+    }
+
+    fn type_ref_val(type_ref: &TypeRef) -> Result<&str, fmt::Error> {
+        Ok( match type_ref {
+            TypeRef::Named(tokens::Token::Identifier {source, ..}) => source,
+            TypeRef::Primitive(tokens::Token::KwTypeI8(_))   => "i8",
+            TypeRef::Primitive(tokens::Token::KwTypeI16(_))  => "i16",
+            TypeRef::Primitive(tokens::Token::KwTypeI32(_))  => "i32",
+            TypeRef::Primitive(tokens::Token::KwTypeI64(_))  => "i64",
+            TypeRef::Primitive(tokens::Token::KwTypeU8(_))   => "u8",
+            TypeRef::Primitive(tokens::Token::KwTypeU16(_))  => "u16",
+            TypeRef::Primitive(tokens::Token::KwTypeU32(_))  => "u32",
+            TypeRef::Primitive(tokens::Token::KwTypeU64(_))  => "u64",
+            TypeRef::Primitive(tokens::Token::KwTypeBool(_)) => "bool",
+            TypeRef::Primitive(tokens::Token::KwTypeF32(_))  => "f32",
+            TypeRef::Primitive(tokens::Token::KwTypeF64(_))  => "f64",
+            TypeRef::Primitive(tokens::Token::KwTypeChar(_)) => "char",
+            _ => return Err(fmt::Error),
+        })
     }
 
     fn integer_base_value(base: &tokens::IntegerBase) -> u8 {
@@ -99,10 +131,293 @@ impl Ast2Json {
             tokens::IntegerBase::Decimal        => 10,
             tokens::IntegerBase::Hexadecimal    => 16,
             tokens::IntegerBase::Binary         => 2,
+            tokens::IntegerBase::Octal          => 8,
         }
     }
 }
 
+/// Object being written by a `JsonWriter`.
+struct Fields {
+    depth: usize,
+    first: bool,
+}
+
+/// Writes the JSON of `Ast2Json` into a sink, `depth` is the nesting level of the written value.
+struct JsonWriter<'w, W: fmt::Write> {
+    out: &'w mut W,
+}
+
+impl<W: fmt::Write> JsonWriter<'_, W> {
+
+    fn expression(&mut self, expr: &Expression, depth: usize) -> fmt::Result {
+        let mut obj = match expr {
+            Expression::Literal(token) => return self.literal(token, depth),
+            Expression::Identifier(token) => {
+                let mut obj = self.begin(depth)?;
+                self.str_field(&mut obj, "type", "identifier")?;
+                self.str_field(&mut obj, "name", Ast2Json::identifier_val(token)?)?;
+                return self.end(obj)
+            },
+            _ => self.begin(depth)?,
+        };
+        match expr {
+            Expression::Binary{lhs, operator, rhs} => {
+                self.str_field(&mut obj, "expression", "binary")?;
+                self.str_field(&mut obj, "operator", Ast2Json::operator_val(operator)?)?;
+                self.expr_field(&mut obj, "lhs", lhs)?;
+                self.expr_field(&mut obj, "rhs", rhs)?;
+            },
+            Expression::Assign{target, operator, value} => {
+                self.str_field(&mut obj, "expression", "assign")?;
+                self.str_field(&mut obj, "operator", Ast2Json::operator_val(operator)?)?;
+                self.expr_field(&mut obj, "target", target)?;
+                self.expr_field(&mut obj, "value", value)?;
+            },
+            Expression::Unary{operator, rhs} => {
+                self.str_field(&mut obj, "expression", "unary")?;
+                self.str_field(&mut obj, "operator", Ast2Json::operator_val(operator)?)?;
+                self.expr_field(&mut obj, "rhs", rhs)?;
+            },
+            Expression::Ref{mutable, expr, ..} => {
+                self.str_field(&mut obj, "expression", "ref")?;
+                self.raw_field(&mut obj, "mutable", mutable)?;
+                self.expr_field(&mut obj, "expr", expr)?;
+            },
+            Expression::Deref{expr, ..} => {
+                self.str_field(&mut obj, "expression", "deref")?;
+                self.expr_field(&mut obj, "expr", expr)?;
+            },
+            Expression::Cast{expr, target} => {
+                self.str_field(&mut obj, "expression", "cast")?;
+                self.str_field(&mut obj, "type", Ast2Json::type_ref_val(target)?)?;
+                self.expr_field(&mut obj, "expr", expr)?;
+            },
+            Expression::Try{expr} => {
+                self.str_field(&mut obj, "expression", "try")?;
+                self.expr_field(&mut obj, "expr", expr)?;
+            },
+            Expression::Call{callee, args, ..} => {
+                self.str_field(&mut obj, "expression", "call")?;
+                self.expr_field(&mut obj, "callee", callee)?;
+                self.array_field(&mut obj, "args", args, JsonWriter::expression)?;
+            },
+            Expression::Member{object, name} => {
+                self.str_field(&mut obj, "expression", "member")?;
+                self.expr_field(&mut obj, "object", object)?;
+                self.str_field(&mut obj, "name", Ast2Json::identifier_val(name)?)?;
+            },
+            Expression::Index{object, index, ..} => {
+                self.str_field(&mut obj, "expression", "index")?;
+                self.expr_field(&mut obj, "object", object)?;
+                self.expr_field(&mut obj, "index", index)?;
+            },
+            Expression::Block{statements, tail, ..} => {
+                self.str_field(&mut obj, "expression", "block")?;
+                self.array_field(&mut obj, "statements", statements, JsonWriter::statement)?;
+                self.optional_field(&mut obj, "tail", tail.as_deref())?;
+            },
+            Expression::If{cond, then_block, else_block, ..} => {
+                self.str_field(&mut obj, "expression", "if")?;
+                self.expr_field(&mut obj, "cond", cond)?;
+                self.expr_field(&mut obj, "then", then_block)?;
+                self.optional_field(&mut obj, "else", else_block.as_deref())?;
+            },
+            Expression::Match{scrutinee, arms, ..} => {
+                self.str_field(&mut obj, "expression", "match")?;
+                self.expr_field(&mut obj, "scrutinee", scrutinee)?;
+                self.array_field(&mut obj, "arms", arms, JsonWriter::arm)?;
+            },
+            Expression::Tuple{elements, ..} => {
+                self.str_field(&mut obj, "expression", "tuple")?;
+                self.array_field(&mut obj, "elements", elements, JsonWriter::expression)?;
+            },
+            Expression::Literal(_) | Expression::Identifier(_) => unreachable!(),
+        }
+        self.end(obj)
+    }
+
+    fn statement(&mut self, stmt: &Statement, depth: usize) -> fmt::Result {
+        if let Statement::Expr(expr) = stmt {
+            return self.expression(expr, depth)
+        }
+        let mut obj = self.begin(depth)?;
+        match stmt {
+            Statement::Let{name, mutable, type_ref, value} => {
+                self.str_field(&mut obj, "statement", "let")?;
+                self.str_field(&mut obj, "name", Ast2Json::identifier_val(name)?)?;
+                self.raw_field(&mut obj, "mutable", mutable)?;
+                match type_ref {
+                    Some(t) => self.str_field(&mut obj, "type", Ast2Json::type_ref_val(t)?)?,
+                    None => self.raw_field(&mut obj, "type", &"null")?,
+                }
+                self.optional_field(&mut obj, "value", value.as_ref())?;
+            },
+            Statement::While{cond, body} => {
+                self.str_field(&mut obj, "statement", "while")?;
+                self.expr_field(&mut obj, "cond", cond)?;
+                self.expr_field(&mut obj, "body", body)?;
+            },
+            Statement::Loop{body} => {
+                self.str_field(&mut obj, "statement", "loop")?;
+                self.expr_field(&mut obj, "body", body)?;
+            },
+            Statement::For{binding, iter, body} => {
+                self.str_field(&mut obj, "statement", "for")?;
+                self.str_field(&mut obj, "binding", Ast2Json::identifier_val(binding)?)?;
+                self.expr_field(&mut obj, "iter", iter)?;
+                self.expr_field(&mut obj, "body", body)?;
+            },
+            Statement::Break(_, value) => {
+                self.str_field(&mut obj, "statement", "break")?;
+                self.optional_field(&mut obj, "value", value.as_ref())?;
+            },
+            Statement::Continue(_) => self.str_field(&mut obj, "statement", "continue")?,
+            Statement::Return(value) => {
+                self.str_field(&mut obj, "statement", "return")?;
+                self.optional_field(&mut obj, "value", value.as_ref())?;
+            },
+            Statement::Expect{condition, implication, message} => {
+                self.str_field(&mut obj, "statement", "expect")?;
+                self.expr_field(&mut obj, "condition", condition)?;
+                self.optional_field(&mut obj, "implication", implication.as_ref())?;
+                match message {
+                    Some(msg) => self.str_field(&mut obj, "message", msg)?,
+                    None => self.raw_field(&mut obj, "message", &"null")?,
+                }
+            },
+            Statement::Expr(_) => unreachable!(),
+        }
+        self.end(obj)
+    }
+
+    fn arm(&mut self, (pattern, value): &(Pattern, Expression), depth: usize) -> fmt::Result {
+        let mut obj = self.begin(depth)?;
+        self.key(&mut obj, "pattern")?;
+        match pattern {
+            Pattern::Literal(token) => self.literal(token, depth + 1)?,
+            Pattern::Binding(token) => {
+                let mut binding = self.begin(depth + 1)?;
+                self.str_field(&mut binding, "type", "binding")?;
+                self.str_field(&mut binding, "name", Ast2Json::identifier_val(token)?)?;
+                self.end(binding)?;
+            },
+            Pattern::Wildcard(_) => {
+                let mut wildcard = self.begin(depth + 1)?;
+                self.str_field(&mut wildcard, "type", "wildcard")?;
+                self.end(wildcard)?;
+            },
+        }
+        self.expr_field(&mut obj, "value", value)?;
+        self.end(obj)
+    }
+
+    fn literal(&mut self, token: &tokens::Token, depth: usize) -> fmt::Result {
+        let mut obj = self.begin(depth)?;
+        match token {
+            tokens::Token::Integer {value, base, source, negative, ..} => {
+                self.str_field(&mut obj, "type", "integer")?;
+                self.raw_field(&mut obj, "base", &Ast2Json::integer_base_value(base))?;
+                self.str_field(&mut obj, "literal", source)?;
+                let sign = if *negative { "-" } else { "" };
+                self.raw_field(&mut obj, "value", &format!("{}{}", sign, value))?;
+            },
+            tokens::Token::FloatNumber {value, source, ..} => {
+                self.str_field(&mut obj, "type", "float")?;
+                self.str_field(&mut obj, "literal", source)?;
+                // infinity, e.g. of '1e999', and NaN are no JSON numbers and written as strings
+                match value.is_finite() {
+                    true => self.raw_field(&mut obj, "value", &format!("{:?}", value))?,
+                    false => self.str_field(&mut obj, "value", &format!("{:?}", value))?,
+                }
+            },
+            tokens::Token::String {source, ..} => {
+                self.str_field(&mut obj, "type", "string")?;
+                self.str_field(&mut obj, "value", source)?;
+            },
+            tokens::Token::Char {ch, ..} => {
+                self.str_field(&mut obj, "type", "char")?;
+                self.str_field(&mut obj, "value", &ch.to_string())?;
+            },
+            tokens::Token::KwTrue(_) | tokens::Token::KwFalse(_) => {
+                self.str_field(&mut obj, "type", "bool")?;
+                self.raw_field(&mut obj, "value", &std::matches!(token, tokens::Token::KwTrue(_)))?;
+            },
+            _ => return Err(fmt::Error),
+        }
+        self.end(obj)
+    }
+
+    fn begin(&mut self, depth: usize) -> Result<Fields, fmt::Error> {
+        self.out.write_char('{')?;
+        Ok( Fields{ depth, first: true } )
+    }
+
+    fn end(&mut self, obj: Fields) -> fmt::Result {
+        write!(self.out, "\n{}}}", "  ".repeat(obj.depth))
+    }
+
+    fn key(&mut self, obj: &mut Fields, key: &str) -> fmt::Result {
+        let separator = if obj.first { "" } else { "," };
+        obj.first = false;
+        write!(self.out, "{}\n{}\"{}\": ", separator, "  ".repeat(obj.depth + 1), key)
+    }
+
+    fn str_field(&mut self, obj: &mut Fields, key: &str, value: &str) -> fmt::Result {
+        self.key(obj, key)?;
+        self.string(value)
+    }
+
+    fn raw_field(&mut self, obj: &mut Fields, key: &str, value: &dyn fmt::Display) -> fmt::Result {
+        self.key(obj, key)?;
+        write!(self.out, "{}", value)
+    }
+
+    fn expr_field(&mut self, obj: &mut Fields, key: &str, expr: &Expression) -> fmt::Result {
+        self.key(obj, key)?;
+        self.expression(expr, obj.depth + 1)
+    }
+
+    fn optional_field(&mut self, obj: &mut Fields, key: &str, expr: Option<&Expression>) -> fmt::Result {
+        match expr {
+            Some(expr) => self.expr_field(obj, key, expr),
+            None => self.raw_field(obj, key, &"null"),
+        }
+    }
+
+    /// Writes an array whose values are nested two levels below the object.
+    fn array_field<T>(&mut self, obj: &mut Fields, key: &str, values: &[T],
+            write: fn(&mut Self, &T, usize) -> fmt::Result) -> fmt::Result {
+        self.key(obj, key)?;
+        if values.is_empty() {
+            return self.out.write_str("[]")
+        }
+        let indent = "  ".repeat(obj.depth + 2);
+        self.out.write_char('[')?;
+        for (index, value) in values.iter().enumerate() {
+            let separator = if index == 0 { "" } else { "," };
+            write!(self.out, "{}\n{}", separator, indent)?;
+            write(self, value, obj.depth + 2)?;
+        }
+        write!(self.out, "\n{}]", "  ".repeat(obj.depth + 1))
+    }
+
+    fn string(&mut self, value: &str) -> fmt::Result {
+        self.out.write_char('"')?;
+        for ch in value.chars() {
+            match ch {
+                '"' => self.out.write_str("\\\"")?,
+                '\\' => self.out.write_str("\\\\")?,
+                '\n' => self.out.write_str("\\n")?,
+                '\r' => self.out.write_str("\\r")?,
+                '\t' => self.out.write_str("\\t")?,
+                c if (c as u32) < 0x20 => write!(self.out, "\\u{:04x}", c as u32)?,
+                c => self.out.write_char(c)?,
+            }
+        }
+        self.out.write_char('"')
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -118,4 +433,328 @@ mod test {
         assert!(!json.is_empty());
         println!("{}",json);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_json_indentation() {
+        let txt = "-1 + 2";
+        let mut prs = super::super::parser::Parser::create(txt.to_string().into_bytes());
+
+        let expr = prs.expression().unwrap();
+        let json = Ast2Json::new().visit_expression(&expr);
+        assert_eq!(json, concat!(
+            "{\n",
+            "  \"expression\": \"binary\",\n",
+            "  \"operator\": \"+\",\n",
+            "  \"lhs\": {\n",
+            "    \"expression\": \"unary\",\n",
+            "    \"operator\": \"-\",\n",
+            "    \"rhs\": {\n",
+            "      \"type\": \"integer\",\n",
+            "      \"base\": 10,\n",
+            "      \"literal\": \"1\",\n",
+            "      \"value\": 1\n",
+            "    }\n",
+            "  },\n",
+            "  \"rhs\": {\n",
+            "    \"type\": \"integer\",\n",
+            "    \"base\": 10,\n",
+            "    \"literal\": \"2\",\n",
+            "    \"value\": 2\n",
+            "  }\n",
+            "}"));
+    }
+
+    #[test]
+    fn test_json_block_array() {
+        let txt = "{ x; \"a\\\"b\" }";
+        let mut prs = super::super::parser::Parser::create(txt.to_string().into_bytes());
+
+        let expr = prs.expression().unwrap();
+        let json = Ast2Json::new().visit_expression(&expr);
+        assert_eq!(json, concat!(
+            "{\n",
+            "  \"expression\": \"block\",\n",
+            "  \"statements\": [\n",
+            "    {\n",
+            "      \"type\": \"identifier\",\n",
+            "      \"name\": \"x\"\n",
+            "    }\n",
+            "  ],\n",
+            "  \"tail\": {\n",
+            "    \"type\": \"string\",\n",
+            "    \"value\": \"a\\\"b\"\n",
+            "  }\n",
+            "}"));
+    }
+
+    #[test]
+    fn test_json_assignment_operators() {
+        let txt = "a <<= b >>= c";
+        let mut prs = super::super::parser::Parser::create(txt.to_string().into_bytes());
+
+        let json = Ast2Json::new().visit_expression(&prs.expression().unwrap());
+        assert!(json.contains("\"operator\": \"<<=\""));
+        assert!(json.contains("\"operator\": \">>=\""));
+    }
+
+    #[test]
+    fn test_json_negative_literal() {
+        let mut prs = super::super::parser::Parser::create("-42".to_string().into_bytes());
+        prs.set_fold_unary_minus_literals(true);
+
+        let json = Ast2Json::new().visit_expression(&prs.expression().unwrap());
+        assert!(json.contains("\"value\": -42"));
+    }
+
+    #[test]
+    fn test_json_float_infinity() {
+        let mut prs = super::super::parser::Parser::create("1e999".to_string().into_bytes());
+
+        let json = Ast2Json::new().visit_expression(&prs.expression().unwrap());
+        assert!(json.contains("\"value\": \"inf\""));
+        assert!(json.contains("\"literal\": \"1e999\""));
+    }
+
+    #[test]
+    fn test_json_postfix() {
+        let mut prs = super::super::parser::Parser::create("f(1)?.x[0]".to_string().into_bytes());
+
+        let json = Ast2Json::new().visit_expression(&prs.expression().unwrap());
+        for kind in ["index", "member", "try", "call"] {
+            assert!(json.contains(&format!("\"expression\": \"{}\"", kind)));
+        }
+        assert!(json.contains("\"args\": [\n"));
+    }
+
+    #[test]
+    fn test_json_tuple() {
+        let mut prs = super::super::parser::Parser::create("(1, x)".to_string().into_bytes());
+
+        let json = Ast2Json::new().visit_expression(&prs.expression().unwrap());
+        assert!(json.contains("\"expression\": \"tuple\""));
+        assert!(json.contains("\"elements\": [\n"));
+        let unit = "()".parse::<Expression>().unwrap();
+        assert_eq!(Ast2Json::new().visit_expression(&unit),
+            "{\n  \"expression\": \"tuple\",\n  \"elements\": []\n}");
+    }
+
+    #[test]
+    fn test_json_malformed_tree() {
+        use util::utf8::Position;
+        let pos = Position{ line: 1, column: 1 };
+        let mut out = String::new();
+        let expr = Expression::Identifier(tokens::Token::Plus(pos));
+        assert_eq!(to_json(&expr, &mut out), Err(fmt::Error));
+
+        let unit = Expression::Tuple{ open: pos, elements: vec![], close: pos };
+        let expr = Expression::Cast{ expr: Box::new(unit), target: TypeRef::Primitive(tokens::Token::KwIf(pos)) };
+        assert_eq!(to_json(&expr, &mut String::new()), Err(fmt::Error));
+        assert_eq!(to_json(&Expression::Literal(tokens::Token::Comma(pos)), &mut String::new()), Err(fmt::Error));
+    }
+
+    #[test]
+    fn test_operator_val() {
+        use util::utf8::Position;
+        let pos = Position{ line: 1, column: 1 };
+        let operators = [
+            (tokens::Token::Less(pos), "<"),
+            (tokens::Token::Greater(pos), ">"),
+            (tokens::Token::LessThan(pos), "<="),
+            (tokens::Token::GreaterThan(pos), ">="),
+            (tokens::Token::Equals(pos), "=="),
+            (tokens::Token::Unequal(pos), "!="),
+            (tokens::Token::ShiftLeft(pos), "<<"),
+            (tokens::Token::ShiftRight(pos), ">>"),
+        ];
+        for (token, op) in operators {
+            assert_eq!(Ast2Json::operator_val(&token), Ok(op));
+            assert_eq!(token.spelling(), op);
+        }
+
+        let mut prs = super::super::parser::Parser::create("a <= b".to_string().into_bytes());
+        let json = Ast2Json::new().visit_expression(&prs.expression().unwrap());
+        assert!(json.contains("\"operator\": \"<=\""));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let txt = concat!("{ let mut x: i32 = f(1, &y); while x < 3 { x += 1; } expect x == 3 => !b : \"ok\"; ",
+            "if a { return; } else { 'c' } (x, *p as u8, -2.5, ()) }");
+        let mut prs = super::super::parser::Parser::create(txt.to_string().into_bytes());
+        let expr = prs.expression().unwrap();
+
+        let mut json = String::new();
+        to_json(&expr, &mut json).unwrap();
+        // output of the serializer before it wrote into a fmt::Write sink
+        assert_eq!(json, concat!(
+            "{\n",
+            "  \"expression\": \"block\",\n",
+            "  \"statements\": [\n",
+            "    {\n",
+            "      \"statement\": \"let\",\n",
+            "      \"name\": \"x\",\n",
+            "      \"mutable\": true,\n",
+            "      \"type\": \"i32\",\n",
+            "      \"value\": {\n",
+            "        \"expression\": \"call\",\n",
+            "        \"callee\": {\n",
+            "          \"type\": \"identifier\",\n",
+            "          \"name\": \"f\"\n",
+            "        },\n",
+            "        \"args\": [\n",
+            "          {\n",
+            "            \"type\": \"integer\",\n",
+            "            \"base\": 10,\n",
+            "            \"literal\": \"1\",\n",
+            "            \"value\": 1\n",
+            "          },\n",
+            "          {\n",
+            "            \"expression\": \"ref\",\n",
+            "            \"mutable\": false,\n",
+            "            \"expr\": {\n",
+            "              \"type\": \"identifier\",\n",
+            "              \"name\": \"y\"\n",
+            "            }\n",
+            "          }\n",
+            "        ]\n",
+            "      }\n",
+            "    },\n",
+            "    {\n",
+            "      \"statement\": \"while\",\n",
+            "      \"cond\": {\n",
+            "        \"expression\": \"binary\",\n",
+            "        \"operator\": \"<\",\n",
+            "        \"lhs\": {\n",
+            "          \"type\": \"identifier\",\n",
+            "          \"name\": \"x\"\n",
+            "        },\n",
+            "        \"rhs\": {\n",
+            "          \"type\": \"integer\",\n",
+            "          \"base\": 10,\n",
+            "          \"literal\": \"3\",\n",
+            "          \"value\": 3\n",
+            "        }\n",
+            "      },\n",
+            "      \"body\": {\n",
+            "        \"expression\": \"block\",\n",
+            "        \"statements\": [\n",
+            "          {\n",
+            "            \"expression\": \"assign\",\n",
+            "            \"operator\": \"+=\",\n",
+            "            \"target\": {\n",
+            "              \"type\": \"identifier\",\n",
+            "              \"name\": \"x\"\n",
+            "            },\n",
+            "            \"value\": {\n",
+            "              \"type\": \"integer\",\n",
+            "              \"base\": 10,\n",
+            "              \"literal\": \"1\",\n",
+            "              \"value\": 1\n",
+            "            }\n",
+            "          }\n",
+            "        ],\n",
+            "        \"tail\": null\n",
+            "      }\n",
+            "    },\n",
+            "    {\n",
+            "      \"statement\": \"expect\",\n",
+            "      \"condition\": {\n",
+            "        \"expression\": \"binary\",\n",
+            "        \"operator\": \"==\",\n",
+            "        \"lhs\": {\n",
+            "          \"type\": \"identifier\",\n",
+            "          \"name\": \"x\"\n",
+            "        },\n",
+            "        \"rhs\": {\n",
+            "          \"type\": \"integer\",\n",
+            "          \"base\": 10,\n",
+            "          \"literal\": \"3\",\n",
+            "          \"value\": 3\n",
+            "        }\n",
+            "      },\n",
+            "      \"implication\": {\n",
+            "        \"expression\": \"unary\",\n",
+            "        \"operator\": \"!\",\n",
+            "        \"rhs\": {\n",
+            "          \"type\": \"identifier\",\n",
+            "          \"name\": \"b\"\n",
+            "        }\n",
+            "      },\n",
+            "      \"message\": \"ok\"\n",
+            "    },\n",
+            "    {\n",
+            "      \"expression\": \"if\",\n",
+            "      \"cond\": {\n",
+            "        \"type\": \"identifier\",\n",
+            "        \"name\": \"a\"\n",
+            "      },\n",
+            "      \"then\": {\n",
+            "        \"expression\": \"block\",\n",
+            "        \"statements\": [\n",
+            "          {\n",
+            "            \"statement\": \"return\",\n",
+            "            \"value\": null\n",
+            "          }\n",
+            "        ],\n",
+            "        \"tail\": null\n",
+            "      },\n",
+            "      \"else\": {\n",
+            "        \"expression\": \"block\",\n",
+            "        \"statements\": [],\n",
+            "        \"tail\": {\n",
+            "          \"type\": \"char\",\n",
+            "          \"value\": \"c\"\n",
+            "        }\n",
+            "      }\n",
+            "    }\n",
+            "  ],\n",
+            "  \"tail\": {\n",
+            "    \"expression\": \"tuple\",\n",
+            "    \"elements\": [\n",
+            "      {\n",
+            "        \"type\": \"identifier\",\n",
+            "        \"name\": \"x\"\n",
+            "      },\n",
+            "      {\n",
+            "        \"expression\": \"cast\",\n",
+            "        \"type\": \"u8\",\n",
+            "        \"expr\": {\n",
+            "          \"expression\": \"deref\",\n",
+            "          \"expr\": {\n",
+            "            \"type\": \"identifier\",\n",
+            "            \"name\": \"p\"\n",
+            "          }\n",
+            "        }\n",
+            "      },\n",
+            "      {\n",
+            "        \"expression\": \"unary\",\n",
+            "        \"operator\": \"-\",\n",
+            "        \"rhs\": {\n",
+            "          \"type\": \"float\",\n",
+            "          \"literal\": \"2.5\",\n",
+            "          \"value\": 2.5\n",
+            "        }\n",
+            "      },\n",
+            "      {\n",
+            "        \"expression\": \"tuple\",\n",
+            "        \"elements\": []\n",
+            "      }\n",
+            "    ]\n",
+            "  }\n",
+            "}"));
+    }
+
+    #[test]
+    fn test_json_octal() {
+        let mut prs = super::super::parser::Parser::create("0o7'55".to_string().into_bytes());
+
+        let json = Ast2Json::new().visit_expression(&prs.expression().unwrap());
+        assert_eq!(json, concat!(
+            "{\n",
+            "  \"type\": \"integer\",\n",
+            "  \"base\": 8,\n",
+            "  \"literal\": \"0o7'55\",\n",
+            "  \"value\": 493\n",
+            "}"));
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+use util::utf8::Position;
+use crate::{Lexer, LexerError};
+use crate::tokens::Token;
+
+/// Errors found by `check_delimiters`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DelimiterError {
+    Unclosed(Position),                             // position of the opening delimiter
+    Mismatched{ open: Position, close: Position },  // closing delimiter of another kind
+    UnexpectedClose(Position),                      // closing delimiter without opening one
+    Lexer(LexerError),
+}
+
+impl DelimiterError {
+
+    /// Returns the position in the source code the error refers to, for mismatched delimiters the
+    /// position of the closing one.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            DelimiterError::Unclosed(pos)
+            | DelimiterError::Mismatched{close: pos, ..}
+            | DelimiterError::UnexpectedClose(pos) => Some(*pos),
+            DelimiterError::Lexer(err) => err.position(),
+        }
+    }
+}
+
+/// Checks that the parentheses, braces and brackets of the source are balanced and correctly
+/// nested without parsing it, delimiters within comments and literals are ignored.
+/// # Returns
+/// The error for the first mismatched or unexpected closing delimiter, or the innermost unclosed
+/// delimiter at the end of the source.
+pub fn check_delimiters(data: &[u8]) -> Result<(), DelimiterError> {
+    let mut open: Vec<Token> = vec![];
+    for token in Lexer::create(data.to_vec()).significant_tokens() {
+        let token = token.map_err(DelimiterError::Lexer)?;
+        if token.is_open() {
+            open.push(token);
+        }
+        else if token.is_close() {
+            let close = token.position();
+            match open.pop() {
+                Some(opening) if opening.closing_delimiter() == Some(token.kind()) => {},
+                Some(opening) => return Err(DelimiterError::Mismatched{
+                    open: opening.position(), close }),
+                None => return Err(DelimiterError::UnexpectedClose(close)),
+            }
+        }
+    }
+    match open.pop() {
+        Some(opening) => Err(DelimiterError::Unclosed(opening.position())),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn position(line: u32, column: u32) -> Position {
+        Position{ line, column }
+    }
+
+    #[test]
+    fn test_balanced() {
+        assert_eq!(check_delimiters(b""), Ok(()));
+        assert_eq!(check_delimiters(b"fn f(a: i32) { x[(1 + 2)] } // )"), Ok(()));
+        assert_eq!(check_delimiters(b"let s = \"(\"; let c = '[';"), Ok(()));
+    }
+
+    #[test]
+    fn test_unclosed() {
+        assert_eq!(check_delimiters(b"f(a, {b}"), Err(DelimiterError::Unclosed(position(1, 2))));
+        assert_eq!(check_delimiters(b"{\n  (\n}"), Err(DelimiterError::Mismatched{
+            open: position(2, 3), close: position(3, 1) }));
+    }
+
+    #[test]
+    fn test_mismatched() {
+        assert_eq!(check_delimiters(b"a(]"), Err(DelimiterError::Mismatched{
+            open: position(1, 2), close: position(1, 3) }));
+        assert_eq!(check_delimiters(b"a)"), Err(DelimiterError::UnexpectedClose(position(1, 2))));
+        assert_eq!(check_delimiters(b"(`)").unwrap_err().position(), Some(position(1, 2)));
+    }
+}
@@ -0,0 +1,117 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+use std::fmt::{Display, Formatter};
+use util::utf8::Position;
+use crate::{LexerError, ParseError};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// Message about a location in the source code reported to the user.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    pub pos: Position,
+    pub severity: Severity,
+}
+
+impl Diagnostic {
+
+    pub fn new(severity: Severity, message: String, pos: Position) -> Diagnostic {
+        Diagnostic{ message, pos, severity }
+    }
+}
+
+impl From<LexerError> for Diagnostic {
+    fn from(err: LexerError) -> Self {
+        Diagnostic::new(Severity::Error, err.message(), err.position().unwrap_or_default())
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(err: ParseError) -> Self {
+        Diagnostic::new(Severity::Error, err.message().to_string(), err.position())
+    }
+}
+
+/// Renders the diagnostics each followed by the source line it refers to and a caret below the
+/// referred column.
+pub fn render(data: &[u8], diags: &[Diagnostic]) -> String {
+    let source = String::from_utf8_lossy(data);
+    let lines: Vec<&str> = source.split(['\n', '\u{0085}', '\u{2028}', '\u{2029}']).collect();
+    let mut out = String::new();
+    for diag in diags {
+        out.push_str(&format!("{}: {}\n --> {}\n", diag.severity, diag.message, diag.pos));
+        out.push_str(&snippet(&lines, diag.pos));
+    }
+    out
+}
+
+/// Formats the source line of the position prefixed by its line number and a caret line below.
+/// Positions outside of the source produce no snippet.
+fn snippet(lines: &[&str], pos: Position) -> String {
+    let line = match (pos.line as usize).checked_sub(1).and_then(|index| lines.get(index)) {
+        Some(line) => line.trim_end_matches('\r'),
+        None => return String::new(),
+    };
+    let number = pos.line.to_string();
+    let gutter = " ".repeat(number.len());
+    let caret = " ".repeat((pos.column as usize).saturating_sub(1));
+    format!("{} |\n{} | {}\n{} | {}^\n", gutter, number, line, gutter, caret)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Lexer, Parser};
+
+    #[test]
+    fn test_render() {
+        let txt = "let a = 1;\nlet b = (2 + 3;\nlet c = `;";
+        let parse_err = Parser::create(txt.to_string().into_bytes()).parse_expression_complete().unwrap_err();
+        let mut lxr = Lexer::create(txt.to_string().into_bytes());
+        let lex_err = loop {
+            if let Err(err) = lxr.get() {
+                break err;
+            }
+        };
+
+        let diags = vec![Diagnostic::from(lex_err), Diagnostic::from(parse_err)];
+        assert_eq!(diags[0].pos, Position{ line: 3, column: 9 });
+        assert_eq!(diags[1].pos, Position{ line: 1, column: 1 });
+        assert_eq!(render(txt.as_bytes(), &diags), concat!(
+            "error: Unexpected character '`'.\n",
+            " --> line: 3, column: 9\n",
+            "  |\n",
+            "3 | let c = `;\n",
+            "  |         ^\n",
+            "error: Expected literal (line: 1, column: 1).\n",
+            " --> line: 1, column: 1\n",
+            "  |\n",
+            "1 | let a = 1;\n",
+            "  | ^\n"));
+    }
+
+    #[test]
+    fn test_render_outside_source() {
+        let diags = vec![Diagnostic::new(Severity::Warning, "Empty file.".to_string(), Position::default())];
+        assert_eq!(render(b"", &diags), "warning: Empty file.\n --> line: 0, column: 0\n");
+    }
+}
@@ -0,0 +1,47 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+use crate::{Lexer, Parser};
+
+/// Fuzz target for the lexer and the parser: scans all tokens of `data` and parses it as a
+/// complete expression.
+/// Arbitrary input must never panic but only yield errors, hence panics are deliberately not
+/// caught so that the fuzzer reports them.
+pub fn fuzz_once(data: &[u8]) {
+    let _ = Lexer::create(data.to_vec()).for_each_token(|_| {});
+    let _ = Parser::create(data.to_vec()).parse_expression_complete();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fuzz_once_adversarial() {
+        let inputs: &[&[u8]] = &[
+            b"",
+            b"\\",
+            b"\"\\",
+            b"'\\",
+            b"\xff\xfe",
+            b"a + \xc3",
+            b"0x\xe2\x82",
+            b"1.5e\xf0\x9f",
+            b"99999999999999999999999999999999999999999999",
+            b"0x1ffffffffffffffffffffffffffffffff",
+            b"1e999999",
+            b"1.7976931348623157e309",
+            b"((((((((",
+            b"))))",
+            b"a[b(c.d{",
+            b"/* unterminated",
+            b"#line 99999999999 \"x",
+            b"-9223372036854775808",
+        ];
+        for data in inputs {
+            fuzz_once(data);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+use util::utf8::Position;
+use crate::{Expression, Statement};
+use crate::tokens::Token;
+use super::AstVisitor;
+
+/// Collects the names and positions of all identifiers referenced by the visited AST nodes in
+/// source order.
+/// # Notes
+/// Only identifier expressions are references: member names (`c.d` refers to `c` only) and names
+/// declared by `let` statements are not collected.
+#[derive(Default)]
+pub struct IdentCollector {
+    idents: Vec<(String, Position)>,
+}
+
+impl IdentCollector {
+
+    pub fn new() -> IdentCollector {
+        IdentCollector{ idents: vec![] }
+    }
+
+    pub fn idents(&self) -> &[(String, Position)] {
+        &self.idents
+    }
+
+    pub fn into_idents(self) -> Vec<(String, Position)> {
+        self.idents
+    }
+}
+
+impl AstVisitor<()> for IdentCollector {
+
+    fn visit_expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Binary{lhs, rhs, ..} => {
+                self.visit_expression(lhs);
+                self.visit_expression(rhs);
+            },
+            Expression::Assign{target, value, ..} => {
+                self.visit_expression(target);
+                self.visit_expression(value);
+            },
+            Expression::Unary{rhs: expr, ..}
+            | Expression::Cast{expr, ..}
+            | Expression::Try{expr}
+            | Expression::Ref{expr, ..}
+            | Expression::Deref{expr, ..}
+            | Expression::Member{object: expr, ..} => self.visit_expression(expr),
+            Expression::Call{callee, args, ..} => {
+                self.visit_expression(callee);
+                args.iter().for_each(|arg| self.visit_expression(arg));
+            },
+            Expression::Index{object, index, ..} => {
+                self.visit_expression(object);
+                self.visit_expression(index);
+            },
+            Expression::Block{statements, tail, ..} => {
+                statements.iter().for_each(|stmt| self.visit_statement(stmt));
+                if let Some(tail) = tail {
+                    self.visit_expression(tail);
+                }
+            },
+            Expression::If{cond, then_block, else_block, ..} => {
+                self.visit_expression(cond);
+                self.visit_expression(then_block);
+                if let Some(else_block) = else_block {
+                    self.visit_expression(else_block);
+                }
+            },
+            Expression::Match{scrutinee, arms, ..} => {
+                // bindings of patterns are declarations, not references
+                self.visit_expression(scrutinee);
+                arms.iter().for_each(|(_, value)| self.visit_expression(value));
+            },
+            Expression::Tuple{elements, ..} => elements.iter().for_each(|element| self.visit_expression(element)),
+            Expression::Identifier(Token::Identifier{start, source, ..}) =>
+                self.idents.push((source.clone(), *start)),
+            Expression::Identifier(_) | Expression::Literal(_) => {},
+        }
+    }
+
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Let{value: Some(expr), ..}
+            | Statement::Expr(expr)
+            | Statement::Return(Some(expr))
+            | Statement::Break(_, Some(expr))
+            | Statement::Loop{body: expr}
+            | Statement::Expect{condition: expr, implication: None, ..} => self.visit_expression(expr),
+            Statement::While{cond: first, body: second}
+            | Statement::For{iter: first, body: second, ..}
+            | Statement::Expect{condition: first, implication: Some(second), ..} => {
+                self.visit_expression(first);
+                self.visit_expression(second);
+            },
+            _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn collect(txt: &str) -> Vec<(String, Position)> {
+        let mut collector = IdentCollector::new();
+        collector.visit_expression(&txt.parse().unwrap());
+        collector.into_idents()
+    }
+
+    #[test]
+    fn test_idents() {
+        assert_eq!(collect("a + foo(b, c.d)"), vec![
+            ("a".to_string(), Position{ line: 1, column: 1 }),
+            ("foo".to_string(), Position{ line: 1, column: 5 }),
+            ("b".to_string(), Position{ line: 1, column: 9 }),
+            ("c".to_string(), Position{ line: 1, column: 12 }),
+        ]);
+    }
+
+    #[test]
+    fn test_idents_block() {
+        let names: Vec<String> = collect("{ let x = y[i]; while x { x = x - 1; } x }")
+            .into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["y", "i", "x", "x", "x", "x"]);
+    }
+}
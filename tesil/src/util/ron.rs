@@ -0,0 +1,394 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+use util::utf8::Position;
+use crate::{Expression, Pattern, Statement, TypeRef};
+use crate::tokens::Token;
+use super::AstVisitor;
+
+/// Serializes the AST into single-line Rusty Object Notation (RON), e.g. for test fixtures.
+/// # Notes
+/// The output follows the layout serde derives for the AST types: struct-like variants as
+/// `Binary(lhs: .., operator: .., rhs: ..)`, positions as `(line: 1, column: 3)` and boxes
+/// transparently. Integer values are `u128`, which the `ron` crate only reads with its
+/// `integer128` feature.
+#[derive(Default)]
+pub struct Ast2Ron {
+    out: String,
+}
+
+impl AstVisitor<String> for Ast2Ron {
+
+    fn visit_expression(&mut self, expr: &Expression) -> String {
+        self.expression(expr);
+        std::mem::take(&mut self.out)
+    }
+
+    fn visit_statement(&mut self, stmt: &Statement) -> String {
+        self.statement(stmt);
+        std::mem::take(&mut self.out)
+    }
+}
+
+/// Returns the expression as RON, see `Ast2Ron`.
+pub fn to_ron(expr: &Expression) -> String {
+    Ast2Ron::new().visit_expression(expr)
+}
+
+impl Ast2Ron {
+
+    pub fn new() -> Ast2Ron {
+        Ast2Ron{ out: String::new() }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Binary{lhs, operator, rhs} => {
+                self.out.push_str("Binary(lhs: ");
+                self.expression(lhs);
+                self.out.push_str(", operator: ");
+                self.token(operator);
+                self.out.push_str(", rhs: ");
+                self.expression(rhs);
+            },
+            Expression::Assign{target, operator, value} => {
+                self.out.push_str("Assign(target: ");
+                self.expression(target);
+                self.out.push_str(", operator: ");
+                self.token(operator);
+                self.out.push_str(", value: ");
+                self.expression(value);
+            },
+            Expression::Unary{operator, rhs} => {
+                self.out.push_str("Unary(operator: ");
+                self.token(operator);
+                self.out.push_str(", rhs: ");
+                self.expression(rhs);
+            },
+            Expression::Ref{operator, mutable, expr} => {
+                self.out.push_str("Ref(operator: ");
+                self.token(operator);
+                self.out.push_str(&format!(", mutable: {}, expr: ", mutable));
+                self.expression(expr);
+            },
+            Expression::Deref{operator, expr} => {
+                self.out.push_str("Deref(operator: ");
+                self.token(operator);
+                self.out.push_str(", expr: ");
+                self.expression(expr);
+            },
+            Expression::Cast{expr, target} => {
+                self.out.push_str("Cast(expr: ");
+                self.expression(expr);
+                self.out.push_str(", target: ");
+                self.type_ref(target);
+            },
+            Expression::Try{expr} => {
+                self.out.push_str("Try(expr: ");
+                self.expression(expr);
+            },
+            Expression::Call{callee, args, close} => {
+                self.out.push_str("Call(callee: ");
+                self.expression(callee);
+                self.out.push_str(", args: ");
+                self.list(args, Ast2Ron::expression);
+                self.out.push_str(", close: ");
+                self.position(close);
+            },
+            Expression::Member{object, name} => {
+                self.out.push_str("Member(object: ");
+                self.expression(object);
+                self.out.push_str(", name: ");
+                self.token(name);
+            },
+            Expression::Index{object, index, close} => {
+                self.out.push_str("Index(object: ");
+                self.expression(object);
+                self.out.push_str(", index: ");
+                self.expression(index);
+                self.out.push_str(", close: ");
+                self.position(close);
+            },
+            Expression::Block{open, statements, tail, close} => {
+                self.out.push_str("Block(open: ");
+                self.position(open);
+                self.out.push_str(", statements: ");
+                self.list(statements, Ast2Ron::statement);
+                self.out.push_str(", tail: ");
+                self.optional(tail.as_deref(), Ast2Ron::expression);
+                self.out.push_str(", close: ");
+                self.position(close);
+            },
+            Expression::If{keyword, cond, then_block, else_block} => {
+                self.out.push_str("If(keyword: ");
+                self.position(keyword);
+                self.out.push_str(", cond: ");
+                self.expression(cond);
+                self.out.push_str(", then_block: ");
+                self.expression(then_block);
+                self.out.push_str(", else_block: ");
+                self.optional(else_block.as_deref(), Ast2Ron::expression);
+            },
+            Expression::Match{keyword, scrutinee, arms, close} => {
+                self.out.push_str("Match(keyword: ");
+                self.position(keyword);
+                self.out.push_str(", scrutinee: ");
+                self.expression(scrutinee);
+                self.out.push_str(", arms: ");
+                self.list(arms, Ast2Ron::arm);
+                self.out.push_str(", close: ");
+                self.position(close);
+            },
+            Expression::Tuple{open, elements, close} => {
+                self.out.push_str("Tuple(open: ");
+                self.position(open);
+                self.out.push_str(", elements: ");
+                self.list(elements, Ast2Ron::expression);
+                self.out.push_str(", close: ");
+                self.position(close);
+            },
+            Expression::Literal(token) => {
+                self.out.push_str("Literal(");
+                self.token(token);
+            },
+            Expression::Identifier(token) => {
+                self.out.push_str("Identifier(");
+                self.token(token);
+            },
+        }
+        self.out.push(')');
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Let{name, mutable, type_ref, value} => {
+                self.out.push_str("Let(name: ");
+                self.token(name);
+                self.out.push_str(&format!(", mutable: {}, type_ref: ", mutable));
+                self.optional(type_ref.as_ref(), Ast2Ron::type_ref);
+                self.out.push_str(", value: ");
+                self.optional(value.as_ref(), Ast2Ron::expression);
+            },
+            Statement::Expr(expr) => {
+                self.out.push_str("Expr(");
+                self.expression(expr);
+            },
+            Statement::While{cond, body} => {
+                self.out.push_str("While(cond: ");
+                self.expression(cond);
+                self.out.push_str(", body: ");
+                self.expression(body);
+            },
+            Statement::Loop{body} => {
+                self.out.push_str("Loop(body: ");
+                self.expression(body);
+            },
+            Statement::For{binding, iter, body} => {
+                self.out.push_str("For(binding: ");
+                self.token(binding);
+                self.out.push_str(", iter: ");
+                self.expression(iter);
+                self.out.push_str(", body: ");
+                self.expression(body);
+            },
+            Statement::Break(token, value) => {
+                self.out.push_str("Break(");
+                self.token(token);
+                self.out.push_str(", ");
+                self.optional(value.as_ref(), Ast2Ron::expression);
+            },
+            Statement::Continue(token) => {
+                self.out.push_str("Continue(");
+                self.token(token);
+            },
+            Statement::Return(value) => {
+                self.out.push_str("Return(");
+                self.optional(value.as_ref(), Ast2Ron::expression);
+            },
+            Statement::Expect{condition, implication, message} => {
+                self.out.push_str("Expect(condition: ");
+                self.expression(condition);
+                self.out.push_str(", implication: ");
+                self.optional(implication.as_ref(), Ast2Ron::expression);
+                self.out.push_str(", message: ");
+                self.optional(message.as_deref(), Ast2Ron::string);
+            },
+        }
+        self.out.push(')');
+    }
+
+    fn arm(&mut self, (pattern, value): &(Pattern, Expression)) {
+        self.out.push('(');
+        match pattern {
+            Pattern::Literal(token) => {
+                self.out.push_str("Literal(");
+                self.token(token);
+            },
+            Pattern::Binding(token) => {
+                self.out.push_str("Binding(");
+                self.token(token);
+            },
+            Pattern::Wildcard(pos) => {
+                self.out.push_str("Wildcard(");
+                self.position(pos);
+            },
+        }
+        self.out.push_str("), ");
+        self.expression(value);
+        self.out.push(')');
+    }
+
+    fn type_ref(&mut self, type_ref: &TypeRef) {
+        let (variant, token) = match type_ref {
+            TypeRef::Primitive(token) => ("Primitive", token),
+            TypeRef::Named(token) => ("Named", token),
+        };
+        self.out.push_str(variant);
+        self.out.push('(');
+        self.token(token);
+        self.out.push(')');
+    }
+
+    fn token(&mut self, token: &Token) {
+        match token {
+            Token::Identifier{start, end, source} => {
+                self.out.push_str("Identifier(start: ");
+                self.position(start);
+                self.out.push_str(", end: ");
+                self.position(end);
+                self.out.push_str(", source: ");
+                self.string(source);
+            },
+            Token::Comment{start, comment} | Token::DocComment{start, comment} => {
+                self.out.push_str(&format!("{:?}(start: ", token.kind()));
+                self.position(start);
+                self.out.push_str(", comment: ");
+                self.string(comment);
+            },
+            Token::Error{pos, message} => {
+                self.out.push_str("Error(pos: ");
+                self.position(pos);
+                self.out.push_str(", message: ");
+                self.string(message);
+            },
+            Token::Integer{start, end, source, digits, value, base, negative} => {
+                self.out.push_str("Integer(start: ");
+                self.position(start);
+                self.out.push_str(", end: ");
+                self.position(end);
+                self.out.push_str(", source: ");
+                self.string(source);
+                self.out.push_str(", digits: ");
+                self.string(digits);
+                self.out.push_str(&format!(", value: {}, base: {:?}, negative: {}", value, base, negative));
+            },
+            Token::FloatNumber{start, end, source, digits, value} => {
+                self.out.push_str("FloatNumber(start: ");
+                self.position(start);
+                self.out.push_str(", end: ");
+                self.position(end);
+                self.out.push_str(", source: ");
+                self.string(source);
+                self.out.push_str(", digits: ");
+                self.string(digits);
+                // debug formatting keeps the fraction of integral values, e.g. '1.0'
+                self.out.push_str(&format!(", value: {:?}", value));
+            },
+            Token::String{start, end, source} => {
+                self.out.push_str("String(start: ");
+                self.position(start);
+                self.out.push_str(", end: ");
+                self.position(end);
+                self.out.push_str(", source: ");
+                self.string(source);
+            },
+            Token::Char{start, ch} => {
+                self.out.push_str("Char(start: ");
+                self.position(start);
+                self.out.push_str(&format!(", ch: {:?}", ch));
+            },
+            _ => {
+                // all other tokens only hold their position
+                self.out.push_str(&format!("{:?}(", token.kind()));
+                self.position(&token.position());
+            },
+        }
+        self.out.push(')');
+    }
+
+    fn position(&mut self, pos: &Position) {
+        self.out.push_str(&format!("(line: {}, column: {})", pos.line, pos.column));
+    }
+
+    fn list<T>(&mut self, values: &[T], write: fn(&mut Self, &T)) {
+        self.out.push('[');
+        for (index, value) in values.iter().enumerate() {
+            if index > 0 {
+                self.out.push_str(", ");
+            }
+            write(self, value);
+        }
+        self.out.push(']');
+    }
+
+    fn optional<T: ?Sized>(&mut self, value: Option<&T>, write: fn(&mut Self, &T)) {
+        match value {
+            Some(value) => {
+                self.out.push_str("Some(");
+                write(self, value);
+                self.out.push(')');
+            },
+            None => self.out.push_str("None"),
+        }
+    }
+
+    fn string(&mut self, value: &str) {
+        self.out.push('"');
+        for ch in value.chars() {
+            match ch {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Parser;
+    use crate::util::balance::check_delimiters;
+
+    #[test]
+    fn test_to_ron_binary() {
+        let expr = Parser::create("1 + 2".to_string().into_bytes()).parse_expression_complete().unwrap();
+        let ron = to_ron(&expr);
+        assert_eq!(ron, concat!(
+            "Binary(",
+            "lhs: Literal(Integer(start: (line: 1, column: 1), end: (line: 1, column: 1), ",
+            "source: \"1\", digits: \"1\", value: 1, base: Decimal, negative: false)), ",
+            "operator: Plus((line: 1, column: 3)), ",
+            "rhs: Literal(Integer(start: (line: 1, column: 5), end: (line: 1, column: 5), ",
+            "source: \"2\", digits: \"2\", value: 2, base: Decimal, negative: false)))"));
+        assert_eq!(check_delimiters(ron.as_bytes()), Ok(()));
+    }
+
+    #[test]
+    fn test_to_ron_statement() {
+        let stmts = Parser::create("return { f(x, 1.0) };".to_string().into_bytes()).parse_statements().unwrap();
+        assert_eq!(Ast2Ron::new().visit_statement(&stmts[0]), concat!(
+            "Return(Some(Block(open: (line: 1, column: 8), statements: [], tail: Some(Call(",
+            "callee: Identifier(Identifier(start: (line: 1, column: 10), end: (line: 1, column: 10), source: \"f\")), ",
+            "args: [Identifier(Identifier(start: (line: 1, column: 12), end: (line: 1, column: 12), source: \"x\")), ",
+            "Literal(FloatNumber(start: (line: 1, column: 15), end: (line: 1, column: 17), ",
+            "source: \"1.0\", digits: \"1.0\", value: 1.0))], close: (line: 1, column: 18))), ",
+            "close: (line: 1, column: 20))))"));
+    }
+}
@@ -0,0 +1,188 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+use util::utf8::Span;
+use crate::{Expression, Statement};
+use super::AstVisitor;
+
+/// Collects the path and the source span of every AST node, e.g. to navigate from a node of the
+/// JSON output of `Ast2Json` to the source code.
+/// Paths start with `$` for the visited node and append the JSON keys of `Ast2Json` for nested
+/// nodes, e.g. `$.rhs.lhs` or `$.statements[1].value`. Nodes without a span are skipped.
+pub struct SpanCollector {
+    path: String,
+    spans: Vec<(String, Span)>,
+}
+
+impl Default for SpanCollector {
+    fn default() -> Self {
+        SpanCollector::new()
+    }
+}
+
+impl SpanCollector {
+
+    pub fn new() -> SpanCollector {
+        SpanCollector{ path: "$".to_string(), spans: vec![] }
+    }
+
+    /// Returns the collected path and span pairs in the order of visiting, parents before children.
+    pub fn spans(&self) -> &[(String, Span)] {
+        &self.spans
+    }
+
+    pub fn into_spans(self) -> Vec<(String, Span)> {
+        self.spans
+    }
+
+    fn record(&mut self, span: Option<Span>) {
+        if let Some(span) = span {
+            self.spans.push((self.path.clone(), span));
+        }
+    }
+
+    fn child_expression(&mut self, key: &str, expr: &Expression) {
+        let len = self.path.len();
+        self.path.push_str(key);
+        self.visit_expression(expr);
+        self.path.truncate(len);
+    }
+
+    fn child_statement(&mut self, key: &str, stmt: &Statement) {
+        let len = self.path.len();
+        self.path.push_str(key);
+        self.visit_statement(stmt);
+        self.path.truncate(len);
+    }
+}
+
+impl AstVisitor<()> for SpanCollector {
+
+    fn visit_expression(&mut self, expr: &Expression) {
+        self.record(expr.span());
+        match expr {
+            Expression::Binary{lhs, rhs, ..} => {
+                self.child_expression(".lhs", lhs);
+                self.child_expression(".rhs", rhs);
+            },
+            Expression::Assign{target, value, ..} => {
+                self.child_expression(".target", target);
+                self.child_expression(".value", value);
+            },
+            Expression::Unary{rhs, ..} => self.child_expression(".rhs", rhs),
+            Expression::Cast{expr, ..} | Expression::Try{expr} | Expression::Ref{expr, ..}
+            | Expression::Deref{expr, ..} => self.child_expression(".expr", expr),
+            Expression::Call{callee, args, ..} => {
+                self.child_expression(".callee", callee);
+                for (index, arg) in args.iter().enumerate() {
+                    self.child_expression(&format!(".args[{}]", index), arg);
+                }
+            },
+            Expression::Member{object, ..} => self.child_expression(".object", object),
+            Expression::Index{object, index, ..} => {
+                self.child_expression(".object", object);
+                self.child_expression(".index", index);
+            },
+            Expression::Block{statements, tail, ..} => {
+                for (index, stmt) in statements.iter().enumerate() {
+                    self.child_statement(&format!(".statements[{}]", index), stmt);
+                }
+                if let Some(tail) = tail {
+                    self.child_expression(".tail", tail);
+                }
+            },
+            Expression::If{cond, then_block, else_block, ..} => {
+                self.child_expression(".cond", cond);
+                self.child_expression(".then", then_block);
+                if let Some(else_block) = else_block {
+                    self.child_expression(".else", else_block);
+                }
+            },
+            Expression::Match{scrutinee, arms, ..} => {
+                self.child_expression(".scrutinee", scrutinee);
+                for (index, (pattern, value)) in arms.iter().enumerate() {
+                    let len = self.path.len();
+                    self.path.push_str(&format!(".arms[{}].pattern", index));
+                    self.record(pattern.span());
+                    self.path.truncate(len);
+                    self.child_expression(&format!(".arms[{}].value", index), value);
+                }
+            },
+            Expression::Tuple{elements, ..} => {
+                for (index, element) in elements.iter().enumerate() {
+                    self.child_expression(&format!(".elements[{}]", index), element);
+                }
+            },
+            Expression::Literal(_) | Expression::Identifier(_) => {},
+        }
+    }
+
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            // expression statements are serialized as the expression itself
+            Statement::Expr(expr) => return self.visit_expression(expr),
+            _ => self.record(stmt.span()),
+        }
+        match stmt {
+            Statement::Let{value: Some(value), ..} | Statement::Return(Some(value))
+            | Statement::Break(_, Some(value)) => self.child_expression(".value", value),
+            Statement::Loop{body} => self.child_expression(".body", body),
+            Statement::For{iter, body, ..} => {
+                self.child_expression(".iter", iter);
+                self.child_expression(".body", body);
+            },
+            Statement::While{cond, body} => {
+                self.child_expression(".cond", cond);
+                self.child_expression(".body", body);
+            },
+            Statement::Expect{condition, implication, ..} => {
+                self.child_expression(".condition", condition);
+                if let Some(implication) = implication {
+                    self.child_expression(".implication", implication);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use util::utf8::Position;
+    use crate::Parser;
+
+    fn span(start: u32, end: u32) -> Span {
+        Span::new(Position{ line: 1, column: start }, Position{ line: 1, column: end })
+    }
+
+    #[test]
+    fn test_spans() {
+        let mut prs = Parser::create("1 + 2 * 3".to_string().into_bytes());
+        let mut collector = SpanCollector::new();
+        collector.visit_expression(&prs.expression().unwrap());
+
+        assert_eq!(collector.into_spans(), vec![
+            ("$".to_string(), span(1, 9)),
+            ("$.lhs".to_string(), span(1, 1)),
+            ("$.rhs".to_string(), span(5, 9)),
+            ("$.rhs.lhs".to_string(), span(5, 5)),
+            ("$.rhs.rhs".to_string(), span(9, 9)),
+        ]);
+    }
+
+    #[test]
+    fn test_spans_block() {
+        let mut prs = Parser::create("{ let x = f(a); x }".to_string().into_bytes());
+        let mut collector = SpanCollector::new();
+        collector.visit_expression(&prs.expression().unwrap());
+
+        let paths: Vec<&str> = collector.spans().iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, vec!["$", "$.statements[0]", "$.statements[0].value",
+            "$.statements[0].value.callee", "$.statements[0].value.args[0]", "$.tail"]);
+        assert_eq!(collector.spans()[0].1, span(1, 19));
+        assert_eq!(collector.spans()[2].1, span(11, 14));
+    }
+}
@@ -0,0 +1,113 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Classification of characters as used by the lexical scanner.
+
+/// Returns whether the character may start an identifier: `_` or a Unicode letter.
+/// # Notes
+/// The Unicode `Alphabetic` property is used as an approximation of `XID_Start` since the standard
+/// library provides no access to the identifier properties.
+pub fn is_ident_start(ch: char) -> bool {
+    ch == '_' || ch.is_alphabetic()
+}
+
+/// Returns whether the character may continue an identifier: `_`, a Unicode letter or number.
+/// # Notes
+/// The Unicode `Alphabetic` and `Numeric` properties are used as an approximation of
+/// `XID_Continue`.
+pub fn is_ident_continue(ch: char) -> bool {
+    ch == '_' || ch.is_alphanumeric()
+}
+
+/// Returns whether the character is whitespace separating tokens: space, tab, line feed,
+/// carriage return, vertical tab or form feed.
+pub fn is_whitespace(ch: char) -> bool {
+    std::matches!(ch, ' ' | '\n' | '\t' | '\r' | '\u{000B}' | '\u{000C}')
+}
+
+/// Returns whether the character ends a line: line feed, next line, line separator or paragraph
+/// separator. A carriage return is not a line break on its own.
+pub fn is_line_break(ch: char) -> bool {
+    std::matches!(ch, '\n' | '\u{0085}' | '\u{2028}' | '\u{2029}')
+}
+
+/// Returns whether the character is a decimal digit: `[0-9]`.
+pub fn is_digit(ch: char) -> bool {
+    ch.is_ascii_digit()
+}
+
+/// Returns whether the character is a hexadecimal digit: `[0-9a-fA-F]`.
+pub fn is_hex_digit(ch: char) -> bool {
+    ch.is_ascii_hexdigit()
+}
+
+/// Returns whether the character is a binary digit: `[01]`.
+pub fn is_bin_digit(ch: char) -> bool {
+    ch == '0' || ch == '1'
+}
+
+/// Returns whether the character is an octal digit: `[0-7]`.
+pub fn is_oct_digit(ch: char) -> bool {
+    ('0'..='7').contains(&ch)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ident() {
+        for ch in ['_', 'a', 'z', 'A', 'Z'] {
+            assert!(is_ident_start(ch));
+            assert!(is_ident_continue(ch));
+        }
+        for ch in ['0', '9'] {
+            assert!(!is_ident_start(ch));
+            assert!(is_ident_continue(ch));
+        }
+        for ch in ['@', '[', '`', '{', '/', ':', '-', ' ', '\u{00b7}', '\u{3000}', '\u{1f600}'] {
+            assert!(!is_ident_start(ch));
+            assert!(!is_ident_continue(ch));
+        }
+    }
+
+    #[test]
+    fn ident_unicode() {
+        for ch in ['é', 'ß', 'λ', 'Ж', '名', '前'] {
+            assert!(is_ident_start(ch));
+            assert!(is_ident_continue(ch));
+        }
+        assert!(!is_ident_start('\u{0663}'));    // arabic-indic digit three
+        assert!(is_ident_continue('\u{0663}'));
+    }
+
+    #[test]
+    fn whitespace() {
+        for ch in [' ', '\n', '\t', '\r', '\u{000B}', '\u{000C}'] {
+            assert!(is_whitespace(ch));
+        }
+        for ch in ['a', '_', '\u{0000}', '\u{00a0}'] {
+            assert!(!is_whitespace(ch));
+        }
+        assert!(is_line_break('\n') && is_line_break('\u{2028}') && !is_line_break('\r'));
+    }
+
+    #[test]
+    fn digits() {
+        assert!(is_digit('0') && is_digit('9'));
+        assert!(!is_digit('/') && !is_digit(':') && !is_digit('a'));
+
+        assert!(is_hex_digit('0') && is_hex_digit('9') && is_hex_digit('a') && is_hex_digit('f'));
+        assert!(is_hex_digit('A') && is_hex_digit('F'));
+        assert!(!is_hex_digit('g') && !is_hex_digit('G') && !is_hex_digit('/') && !is_hex_digit('@'));
+
+        assert!(is_bin_digit('0') && is_bin_digit('1'));
+        assert!(!is_bin_digit('2') && !is_bin_digit('/'));
+
+        assert!(is_oct_digit('0') && is_oct_digit('7'));
+        assert!(!is_oct_digit('8') && !is_oct_digit('/'));
+    }
+}
@@ -3,4 +3,9 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
+// Decoding failures carry no further information, hence the `Result<_, ()>` signatures.
+#![allow(clippy::result_unit_err)]
+
+pub mod chars;
+pub mod source;
 pub mod utf8;
@@ -0,0 +1,45 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Mapping of positions to the UTF-8 encoded source data they have been counted in.
+
+use std::ops::Range;
+use crate::utf8::{Position, Span};
+
+/// Returns the range of bytes of the characters from `start` to `end` (inclusive) within the data
+/// by scanning it from the beginning, e.g. for tokens that keep no byte offsets.
+/// # Returns
+/// `None` if the positions do not refer to characters of the data, see `Span::byte_range`.
+pub fn byte_range(data: &[u8], start: Position, end: Position) -> Option<Range<usize>> {
+    Span::new(start, end).byte_range(data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn position(line: u32, column: u32) -> Position {
+        Position{ line, column }
+    }
+
+    #[test]
+    fn multi_line() {
+        let data = "fn f() {\n  g(\u{00e4});\n}\n".as_bytes();
+        assert_eq!(byte_range(data, position(1, 1), position(1, 2)), Some( 0..2 ));
+        assert_eq!(byte_range(data, position(2, 3), position(2, 6)), Some( 11..16 ));
+        assert_eq!(byte_range(data, position(2, 5), position(2, 5)), Some( 13..15 ));
+        assert_eq!(byte_range(data, position(1, 8), position(3, 1)), Some( 7..19 ));
+        assert_eq!(std::str::from_utf8(&data[11..16]), Ok( "g(\u{00e4})" ));
+    }
+
+    #[test]
+    fn outside_data() {
+        let data = b"ab\ncd";
+        assert_eq!(byte_range(data, position(2, 2), position(2, 3)), None);
+        assert_eq!(byte_range(data, position(3, 1), position(3, 1)), None);
+        assert_eq!(byte_range(data, position(2, 2), position(1, 1)), None);
+    }
+}
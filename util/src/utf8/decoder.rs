@@ -12,11 +12,18 @@
 ///  assert_eq!(decoder.decode(0xc2), Ok( None ));
 ///  assert_eq!(decoder.decode(0xa2), Ok( Some('\u{00a2}')));
 /// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Decoder {
     code: u32,
     remaining: u32,
 }
 
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder::new()
+    }
+}
+
 impl Decoder {
 
     /// Creates a new UTF-8 byte sequence decoder in initial state.
@@ -30,6 +37,16 @@ impl Decoder {
         self.remaining = 0
     }
 
+    /// Returns whether the decoder is within a UTF-8 sequence waiting for further bytes.
+    pub fn is_pending(&self) -> bool {
+        self.remaining != 0
+    }
+
+    /// Returns the number of bytes still expected to complete the current UTF-8 sequence.
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
     /// Decodes another byte and returns:
     /// - Ok(None):     if the sequence is not complete, further bytes are expected
     /// - Ok(Some(ch)): if the sequence is completed and a 32 bit long unicode character is returned
@@ -43,6 +60,33 @@ impl Decoder {
         }
     }
 
+    /// Decodes a whole slice of bytes and appends the decoded characters to `out`.
+    /// An incomplete sequence at the end of the slice is kept in the decoder's state and completed
+    /// by the bytes of the next call.
+    /// # Returns
+    /// - Ok(n):    the number of consumed bytes, which is always the length of the slice
+    /// - Err(i):   the index of the first invalid byte, the characters before it have been appended
+    pub fn decode_all(&mut self, bytes: &[u8], out: &mut String) -> Result<usize, usize> {
+        for (index, byte) in bytes.iter().enumerate() {
+            if self.remaining == 0 && *byte < 0x80 {
+                out.push(*byte as char);
+                continue;
+            }
+            match self.decode(*byte) {
+                Ok( Some( ch )) => out.push(ch),
+                Ok( None ) => {},
+                Err(()) => return Err(index),
+            }
+        }
+        Ok( bytes.len() )
+    }
+
+    /// Returns the bytes following a leading UTF-8 byte order mark (`EF BB BF`), or all bytes if
+    /// they do not start with one.
+    pub fn strip_bom(bytes: &[u8]) -> &[u8] {
+        bytes.strip_prefix(&[0xef, 0xbb, 0xbf]).unwrap_or(bytes)
+    }
+
     fn decode_ready(&mut self, byte: u8) -> Result< Option<char>, () > {
         if 0x00 == (byte & 0x80) {
             Decoder::finalize_char(byte as u32)
@@ -94,6 +138,49 @@ impl Decoder {
 mod test {
     use super::Decoder;
 
+    #[test]
+    fn pending() {
+        let mut decoder = Decoder::new();
+        assert!(!decoder.is_pending());
+        assert_eq!(decoder.decode(0xc2), Ok( None ));
+        assert!(decoder.is_pending());
+        assert_eq!(decoder.remaining(), 1);
+        assert_eq!(decoder.decode(0xa2), Ok( Some( '\u{00a2}' )));
+        assert!(!decoder.is_pending());
+
+        assert_eq!(decoder.decode(0xf0), Ok( None ));
+        assert_eq!(decoder.remaining(), 3);
+        decoder.reset();
+        assert_eq!(decoder.remaining(), 0);
+    }
+
+    #[test]
+    fn decode_all() {
+        let mut decoder = Decoder::new();
+        let mut out = String::new();
+        let txt = "abc \u{00a2}\u{20ac}\u{10348}";
+        assert_eq!(decoder.decode_all(txt.as_bytes(), &mut out), Ok( txt.len() ));
+        assert_eq!(out, txt);
+    }
+
+    #[test]
+    fn decode_all_incomplete() {
+        let mut decoder = Decoder::new();
+        let mut out = String::new();
+        assert_eq!(decoder.decode_all(&[0x41, 0xe2, 0x82], &mut out), Ok( 3 ));
+        assert_eq!(out, "A");
+        assert_eq!(decoder.decode_all(&[0xac, 0x42], &mut out), Ok( 2 ));
+        assert_eq!(out, "A\u{20ac}B");
+    }
+
+    #[test]
+    fn decode_all_invalid() {
+        let mut decoder = Decoder::new();
+        let mut out = String::new();
+        assert_eq!(decoder.decode_all(&[0x41, 0x42, 0xff, 0x43], &mut out), Err( 2 ));
+        assert_eq!(out, "AB");
+    }
+
     #[test]
     fn valid_utf8_single() {
         let mut decoder = Decoder::new();
@@ -164,4 +251,13 @@ mod test {
         assert_eq!(decoder.decode(0xf2), Ok( None ));
         assert_eq!(decoder.decode(0x7f), Err(()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn strip_bom() {
+        assert_eq!(Decoder::strip_bom(b"\xef\xbb\xbfabc"), b"abc");
+        assert_eq!(Decoder::strip_bom(b"\xef\xbb\xbf"), b"");
+        assert_eq!(Decoder::strip_bom(b"abc"), b"abc");
+        assert_eq!(Decoder::strip_bom(b"\xef\xbbabc"), b"\xef\xbbabc");
+        assert_eq!(Decoder::strip_bom(b""), b"");
+    }
+}
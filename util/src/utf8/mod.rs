@@ -9,3 +9,7 @@ pub use decoder::Decoder;
 mod stream;
 pub use stream::Stream;
 pub use stream::Position;
+pub use stream::Span;
+pub use stream::LineEndingMode;
+pub use stream::Utf8Error;
+pub use stream::Encoding;
@@ -3,42 +3,191 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
+use crate::chars;
 use super::Decoder;
 use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::rc::Rc;
 
 /// Position within a text file.
-#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+/// Positions are ordered by line first and then by column.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
 pub struct Position {
     pub line: u32,
     pub column: u32,
 }
 
+impl Position {
+
+    /// Returns the position `n` columns further on the same line, saturating at the maximum column.
+    pub fn advanced(self, n: u32) -> Position {
+        Position{ line: self.line, column: self.column.saturating_add(n) }
+    }
+}
+
 impl Display for Position {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "line: {}, column: {}", self.line, self.column)
     }
 }
 
+/// Range of text between two positions within a text file, both ends are inclusive.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+
+    pub fn new(start: Position, end: Position) -> Span {
+        Span{ start, end }
+    }
+
+    /// Returns whether the position lies within the span including its start and end.
+    pub fn contains(&self, pos: Position) -> bool {
+        self.start <= pos && pos <= self.end
+    }
+
+    /// Returns the range of bytes within the UTF-8 encoded data that covers the characters from the
+    /// start to the end of the span, positions being counted as by a `Stream` reading the data.
+    /// # Returns
+    /// `None` if the span does not start and end at characters of the data or if the data up to the
+    /// end of the span is not valid UTF-8.
+    pub fn byte_range(&self, data: &[u8]) -> Option<Range<usize>> {
+        let mut dec = Decoder::new();
+        let mut pos = Position{ line: 1, column: 0 };
+        let mut char_start = 0;
+        let mut start = None;
+        for (index, byte) in data.iter().enumerate() {
+            match dec.decode(*byte) {
+                Ok( Some( ch )) => {
+                    pos = Stream::next_position(pos, ch);
+                    if pos == self.start {
+                        start = Some(char_start);
+                    }
+                    if pos == self.end {
+                        return start.map(|start| start..index + 1)
+                    }
+                    char_start = index + 1;
+                },
+                Ok( None ) => {},
+                Err(()) => return None,
+            }
+        }
+        None
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} - {}", self.start, self.end)
+    }
+}
+
+/// Invalid UTF-8 sequence found by `Stream::get_checked()`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Utf8Error {
+    pub byte_offset: usize,     // offset of the first byte of the invalid sequence within the data
+    pub position: Position,     // position the invalid sequence occupies
+}
+
+impl Display for Utf8Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid UTF-8 at byte {} ({})", self.byte_offset, self.position)
+    }
+}
+
+/// Selects the characters that end a line when counting the positions of a `Stream`.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum LineEndingMode {
+    LfOnly,         // only line feed
+    LfCr,           // line feed, carriage return and the pair of both counted as a single line break
+    #[default]
+    Unicode,        // line breaks of `chars::is_line_break`
+}
+
+impl LineEndingMode {
+
+    /// Returns whether the character ends a line in this mode.
+    pub fn is_line_break(self, ch: char) -> bool {
+        match self {
+            LineEndingMode::LfOnly => ch == '\n',
+            LineEndingMode::LfCr => ch == '\n' || ch == '\r',
+            LineEndingMode::Unicode => chars::is_line_break(ch),
+        }
+    }
+}
+
+/// Character encoding of the data of a `Stream`.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Latin1,         // ISO 8859-1, each byte is the code point of a character
+}
+
 /// A UTF-8 encoded stream of characters readable in forward manner with peek (look-ahead) function.
 /// The struct implements next to the 'get()' method to retrieve and consume the next character also
 /// the 'Iterator' trait with its 'next()' method. The difference lies in the returned value, the
 /// 'get()' method is a little bit more suitable for our later purposes in the lexer.
 ///
-/// #TODO
-/// - resynchronization after UTF-8 encoding failures needs to be implemented
+/// The stream can be cloned to take a snapshot of its state, e.g. for speculative scanning. The
+/// data is shared between the clones so that cloning is cheap.
+///
+/// Legacy sources can be read in Latin-1 instead, see `Stream::create_with_encoding()`.
+///
+#[derive(Clone, PartialEq, Debug)]
 pub struct Stream {
-    data: Vec<u8>,
+    data: Rc<[u8]>,
     index: usize,
+    consumed: usize,    // index of the first byte not read by 'get()' yet
     dec: Decoder,
     pos: Position,
     peeked: Option< Result< Option<char>, () > >,
     error: bool,
+    line_ending: LineEndingMode,
+    after_cr: bool,     // last character was a carriage return, see `LineEndingMode::LfCr`
+    encoding: Encoding,
 }
 
 impl Stream {
 
     pub fn create(data: Vec<u8>) -> Stream {
-        Stream{ data, index: 0, dec: Decoder::new(), pos: Position{ line: 1, column: 0}, peeked: None, error: false }
+        Stream::create_with_encoding(data, Encoding::Utf8)
+    }
+
+    /// Creates a stream decoding the data in the given encoding. In Latin-1 every byte is mapped
+    /// directly to the code point of the same value, so that decoding never fails. The bytes
+    /// 0x80 - 0x9f of Windows-1252 are thereby read as C1 control characters.
+    pub fn create_with_encoding(data: Vec<u8>, encoding: Encoding) -> Stream {
+        Stream{ data: data.into(), index: 0, consumed: 0, dec: Decoder::new(), pos: Position{ line: 1, column: 0}, peeked: None, error: false,
+            line_ending: LineEndingMode::default(), after_cr: false, encoding }
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Sets the characters that increment the line of the stream's position, all Unicode line
+    /// breaks are used by default.
+    pub fn set_line_ending_mode(&mut self, mode: LineEndingMode) {
+        self.line_ending = mode;
+    }
+
+    pub fn line_ending_mode(&self) -> LineEndingMode {
+        self.line_ending
+    }
+
+    /// Rewinds the stream to the start of its data, e.g. to read it again.
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.consumed = 0;
+        self.dec.reset();
+        self.pos = Position{ line: 1, column: 0 };
+        self.peeked = None;
+        self.error = false;
+        self.after_cr = false;
     }
 
     /// Returns the current position of the stream.
@@ -51,6 +200,59 @@ impl Stream {
         self.pos
     }
 
+    /// Returns the complete data of the stream independent of the read position.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the offset of the next byte to be read by `get()` within the data, a peeked character
+    /// is not consumed yet.
+    pub fn byte_position(&self) -> usize {
+        self.consumed
+    }
+
+    /// Returns the number of bytes of the data not read by `get()` yet.
+    pub fn remaining_bytes(&self) -> usize {
+        self.data.len() - self.consumed
+    }
+
+    /// Returns the original text from the character at `start` up to but excluding the character
+    /// at `end`. Positions are the ones the stream reports after reading a character as for a
+    /// `Span`, `end` may be the column following the last character to extract up to the end of the
+    /// data. The text is decoded from the start of the data, the read position is not changed.
+    /// # Returns
+    /// `None` if `start` or `end` are not positions of characters of the data, if `end` precedes
+    /// `start` or if the data up to `end` is not valid UTF-8.
+    pub fn text_between(&self, start: Position, end: Position) -> Option<String> {
+        let mut stream = self.clone();
+        stream.reset();
+        let mut text: Option<String> = None;
+        loop {
+            let ch = stream.get().ok()?;
+            let pos = match ch {
+                Some(_) => stream.pos(),
+                None => stream.pos().advanced(1),
+            };
+            if pos == start && text.is_none() {
+                text = Some(String::new());
+            }
+            if pos == end {
+                return text
+            }
+            match (ch, text.as_mut()) {
+                (None, _) => return None,
+                (Some(ch), Some(text)) => text.push(ch),
+                (Some(_), None) => {},
+            }
+        }
+    }
+
+    /// Overrides the line of the current position, the following lines are counted from it, e.g.
+    /// for line directives of generated sources.
+    pub fn set_line(&mut self, line: u32) {
+        self.pos.line = line;
+    }
+
     /// Returns the next character from the UTF-8 stream data.
     /// # Returns
     /// - Ok( Some( ch ) )      A valid UTF-8 character has been detected, file position had been updated.
@@ -67,8 +269,9 @@ impl Stream {
             Some(_) => self.peeked.take().unwrap(),
             None => self.get_next_char(),
         };
+        self.consumed = self.index;
 
-        return match val {
+        match val {
             Ok( Some( ch )) => {
                 self.advance_position(ch);
                 Ok( Some( ch ))
@@ -81,6 +284,21 @@ impl Stream {
         }
     }
 
+    /// Returns the next character like `get()` but reports an encoding error with the offset and
+    /// position of the invalid byte sequence.
+    pub fn get_checked(&mut self) -> Result< Option<char>, Utf8Error > {
+        let byte_offset = self.consumed;
+        let position = self.pos.advanced(1);
+        self.get().map_err(|()| Utf8Error{ byte_offset, position })
+    }
+
+    /// Returns the next character like `get()` together with the position the character occupies,
+    /// which is the position after it unless the character ends a line.
+    pub fn get_with_pos(&mut self) -> Result< Option<(char, Position)>, () > {
+        let pos = self.pos.advanced(1);
+        Ok( self.get()?.map(|ch| (ch, pos)) )
+    }
+
     /// Consume the next character without returning it.
     /// This method is usually used in conjunction with peek and will panic if the next character
     /// is an error!
@@ -98,16 +316,22 @@ impl Stream {
         if self.peeked.is_none() {
             self.peeked = Some( self.get_next_char() );
         }
-        return self.peeked.as_ref().unwrap().clone();
+        *self.peeked.as_ref().unwrap()
     }
 
     fn get_next_char(&mut self) -> Result< Option<char>, () > {
         if self.index >= self.data.len() {
             return Ok( None )
         }
+        if self.encoding == Encoding::Latin1 {
+            self.index += 1;
+            return Ok( Some( self.data[self.index - 1] as char ))
+        }
 
+        let start = self.index;
         loop {
-            let r = self.dec.decode(self.data[self.index]);
+            let byte = self.data[self.index];
+            let r = self.dec.decode(byte);
             self.index += 1;
             match r {
                 Ok( None ) => {
@@ -116,25 +340,102 @@ impl Stream {
                     }
                 },
                 Ok( Some( ch )) => return Ok( Some( ch )),
-                Err(()) => return Err(()),
+                Err(()) => {
+                    // a byte starting a new sequence that interrupts an incomplete one is kept
+                    if self.index - 1 > start && Stream::is_sequence_start(byte) {
+                        self.index -= 1;
+                    }
+                    return Err(())
+                },
             }
         }
     }
 
+    /// Resynchronizes the stream after an UTF-8 encoding error so that reading can continue.
+    /// The invalid byte sequence is skipped and counted as a single character for the position.
+    /// # Returns
+    /// Whether the stream had been in an error condition, including an error returned by 'peek()'.
+    pub fn resync(&mut self) -> bool {
+        if let Some(Err(())) = self.peeked {
+            self.peeked = None;
+            self.error = true;
+        }
+        if !self.error {
+            return false
+        }
+        self.dec.reset();
+        while self.index < self.data.len() && !Stream::is_sequence_start(self.data[self.index]) {
+            self.index += 1;
+        }
+        self.consumed = self.index;
+        self.pos.column = self.pos.column.saturating_add(1);
+        self.error = false;
+        true
+    }
+
+    fn is_sequence_start(byte: u8) -> bool {
+        byte < 0x80 || (0xc0..0xf8).contains(&byte)
+    }
+
     fn advance_position(&mut self, ch: char) {
-        match ch {
-            '\n' | '\u{0085}' | '\u{2028}' | '\u{2029}' => {
-                self.pos.line += 1;
-                self.pos.column = 0;
-            },
-            _ => {
-                self.pos.column += 1;
+        let after_cr = std::mem::replace(&mut self.after_cr, ch == '\r');
+        if self.line_ending == LineEndingMode::LfCr && after_cr && ch == '\n' {
+            // the line feed of a CR LF pair, the line has been ended by the carriage return
+            return
+        }
+        self.pos = Stream::next_position_in(self.pos, ch, self.line_ending);
+    }
+
+    fn next_position(pos: Position, ch: char) -> Position {
+        Stream::next_position_in(pos, ch, LineEndingMode::Unicode)
+    }
+
+    fn next_position_in(pos: Position, ch: char, mode: LineEndingMode) -> Position {
+        if mode.is_line_break(ch) {
+            Position{ line: pos.line.saturating_add(1), column: 0 }
+        }
+        else {
+            // columns of pathologically long lines stick at the maximum instead of overflowing
+            pos.advanced(1)
+        }
+    }
+
+    /// Counts the lines of UTF-8 encoded data without creating a stream. A line is terminated by
+    /// one of the line breaks the stream's positions are based on, a carriage return is not a line
+    /// break on its own. A last line without line break is counted if it is not empty.
+    /// # Returns
+    /// - Ok(n):    the number of lines, 0 for empty data
+    /// - Err(pos): the position of the first invalid UTF-8 sequence
+    pub fn count_lines(data: &[u8]) -> Result<u32, Position> {
+        let mut dec = Decoder::new();
+        let mut pos = Position{ line: 1, column: 0 };
+        for byte in data {
+            match dec.decode(*byte) {
+                Ok( Some( ch )) => pos = Stream::next_position(pos, ch),
+                Ok( None ) => {},
+                Err(()) => return Err(pos.advanced(1)),
             }
         }
+        if dec.is_pending() {
+            return Err(pos.advanced(1))
+        }
+        Ok( if pos.column == 0 { pos.line - 1 } else { pos.line } )
     }
 
 }
 
+impl From<&str> for Stream {
+    fn from(txt: &str) -> Self {
+        Stream::create(txt.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Stream {
+    fn from(txt: String) -> Self {
+        Stream::create(txt.into_bytes())
+    }
+}
+
 impl Iterator for Stream {
     type Item = Result<char, ()>;
 
@@ -149,7 +450,228 @@ impl Iterator for Stream {
 
 #[cfg(test)]
 mod test {
-    use super::{Stream, Position};
+    use super::{Stream, Position, Span, LineEndingMode, Utf8Error, Encoding};
+
+    #[test]
+    fn clone_mid_read() {
+        let txt = "ab\ncd \u{00f9}e";
+        let mut first = Stream::create(txt.to_string().into_bytes());
+        first.advance();
+        first.advance();
+        assert_eq!(first.peek(), Ok( Some('\n') ));
+
+        let mut second = first.clone();
+        assert_eq!(first, second);
+        loop {
+            let pos = first.pos();
+            let ch = first.get();
+            assert_eq!(second.get(), ch);
+            assert_eq!(second.pos(), first.pos());
+            assert!(first.pos() >= pos);
+            if ch == Ok( None ) {
+                break;
+            }
+        }
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn position_advanced() {
+        assert_eq!(Position{ line: 3, column: 4 }.advanced(2), Position{ line: 3, column: 6 });
+        assert_eq!(Position{ line: 3, column: 4 }.advanced(0), Position{ line: 3, column: 4 });
+    }
+
+    #[test]
+    fn span_byte_range() {
+        let data = "ab\nc\u{e4}d\n".as_bytes();
+        let span = |l1, c1, l2, c2| Span::new(Position{ line: l1, column: c1 }, Position{ line: l2, column: c2 });
+        assert_eq!(span(1, 1, 1, 2).byte_range(data), Some( 0..2 ));
+        assert_eq!(span(1, 2, 2, 3).byte_range(data), Some( 1..7 ));
+        assert_eq!(span(2, 2, 2, 2).byte_range(data), Some( 4..6 ));
+        assert_eq!(span(2, 3, 2, 5).byte_range(data), None);
+        assert_eq!(span(1, 1, 1, 2).byte_range(b"a\xffb"), None);
+    }
+
+    #[test]
+    fn count_lines() {
+        assert_eq!(Stream::count_lines(b""), Ok( 0 ));
+        assert_eq!(Stream::count_lines(b"abc"), Ok( 1 ));
+        assert_eq!(Stream::count_lines(b"a\nb\n"), Ok( 2 ));
+        assert_eq!(Stream::count_lines(b"a\nb\n\nc"), Ok( 4 ));
+        assert_eq!(Stream::count_lines(b"a\rb\r"), Ok( 1 ));
+        assert_eq!(Stream::count_lines(b"a\r\nb\nc\r\n"), Ok( 3 ));
+        assert_eq!(Stream::count_lines("a\u{2028}b\u{0085}".as_bytes()), Ok( 2 ));
+        assert_eq!(Stream::count_lines(b"a\nb\xffc"), Err( Position{ line: 2, column: 2 } ));
+        assert_eq!(Stream::count_lines(b"a\xe2\x82"), Err( Position{ line: 1, column: 2 } ));
+    }
+
+    #[test]
+    fn column_saturates() {
+        let mut stream: Stream = "abc\nd".into();
+        stream.pos.column = u32::MAX - 1;
+        assert_eq!(stream.get(), Ok( Some('a') ));
+        assert_eq!(stream.pos(), Position{ line: 1, column: u32::MAX });
+        assert_eq!(stream.get(), Ok( Some('b') ));
+        assert_eq!(stream.get(), Ok( Some('c') ));
+        assert_eq!(stream.pos(), Position{ line: 1, column: u32::MAX });
+        assert_eq!(stream.get(), Ok( Some('\n') ));
+        assert_eq!(stream.get(), Ok( Some('d') ));
+        assert_eq!(stream.pos(), Position{ line: 2, column: 1 });
+        assert_eq!(Position{ line: 1, column: u32::MAX }.advanced(2), Position{ line: 1, column: u32::MAX });
+    }
+
+    #[test]
+    fn line_ending_mode() {
+        let lines = |mode, txt: &str| {
+            let mut stream: Stream = txt.into();
+            stream.set_line_ending_mode(mode);
+            while stream.get() != Ok( None ) {}
+            stream.pos()
+        };
+        assert_eq!(Stream::from("").line_ending_mode(), LineEndingMode::Unicode);
+        assert_eq!(lines(LineEndingMode::Unicode, "a\u{2028}b"), Position{ line: 2, column: 1 });
+        assert_eq!(lines(LineEndingMode::LfOnly, "a\u{2028}b"), Position{ line: 1, column: 3 });
+        assert_eq!(lines(LineEndingMode::LfOnly, "a\rb\n"), Position{ line: 2, column: 0 });
+        assert_eq!(lines(LineEndingMode::LfCr, "a\rb\r\nc\n\rd"), Position{ line: 5, column: 1 });
+        assert_eq!(lines(LineEndingMode::LfCr, "a\u{2028}b"), Position{ line: 1, column: 3 });
+    }
+
+    #[test]
+    fn byte_position() {
+        let mut stream: Stream = "a\u{00f9}b".into();
+        assert_eq!((stream.byte_position(), stream.remaining_bytes()), (0, 4));
+        assert_eq!(stream.get(), Ok( Some('a') ));
+        assert_eq!(stream.peek(), Ok( Some('\u{00f9}') ));
+        assert_eq!((stream.byte_position(), stream.remaining_bytes()), (1, 3));
+        assert_eq!(stream.get(), Ok( Some('\u{00f9}') ));
+        assert_eq!(stream.byte_position(), 3);
+        assert_eq!(stream.get(), Ok( Some('b') ));
+        assert_eq!(stream.get(), Ok( None ));
+        assert_eq!((stream.byte_position(), stream.remaining_bytes()), (4, 0));
+        stream.reset();
+        assert_eq!(stream.byte_position(), 0);
+
+        let mut stream = Stream::create(vec![0xff, 0x80, b'b']);
+        assert_eq!(stream.get(), Err(()));
+        assert!(stream.resync());
+        assert_eq!(stream.byte_position(), 2);
+    }
+
+    #[test]
+    fn text_between() {
+        let mut stream: Stream = "let x = f\u{00f9}(1);\nbar".into();
+        stream.advance();
+        let pos = |line, column| Position{ line, column };
+        assert_eq!(stream.text_between(pos(1, 9), pos(1, 14)), Some( "f\u{00f9}(1)".to_string() ));
+        assert_eq!(stream.text_between(pos(1, 14), pos(2, 2)), Some( ";\nb".to_string() ));
+        assert_eq!(stream.text_between(pos(2, 1), pos(2, 4)), Some( "bar".to_string() ));
+        assert_eq!(stream.text_between(pos(1, 5), pos(1, 5)), Some( String::new() ));
+        assert_eq!(stream.text_between(pos(1, 9), pos(1, 5)), None);
+        assert_eq!(stream.text_between(pos(1, 9), pos(1, 20)), None);
+        assert_eq!(stream.text_between(pos(2, 1), pos(2, 5)), None);
+        assert_eq!(stream.pos(), pos(1, 1));
+
+        let stream = Stream::create(b"ab\xffcd".to_vec());
+        assert_eq!(stream.text_between(Position{ line: 1, column: 1 }, Position{ line: 1, column: 3 }), None);
+    }
+
+    #[test]
+    fn encoding() {
+        let data = b"caf\xe9\n".to_vec();
+        let mut stream = Stream::create_with_encoding(data.clone(), Encoding::Latin1);
+        assert_eq!(stream.encoding(), Encoding::Latin1);
+        assert_eq!(stream.by_ref().collect::<Result<String, ()>>(), Ok( "caf\u{00e9}\n".to_string() ));
+        assert_eq!(stream.pos(), Position{ line: 2, column: 0 });
+        assert_eq!(stream.byte_position(), 5);
+
+        // no Windows-1252 mapping, 0x80 is the C1 control character and not '€'
+        let mut stream = Stream::create_with_encoding(vec![0x80], Encoding::Latin1);
+        assert_eq!(stream.get(), Ok( Some('\u{0080}') ));
+
+        let mut stream = Stream::create(data);
+        assert_eq!(stream.encoding(), Encoding::Utf8);
+        for ch in "caf".chars() {
+            assert_eq!(stream.get(), Ok( Some(ch) ));
+        }
+        assert_eq!(stream.get(), Err(()));
+    }
+
+    #[test]
+    fn set_line() {
+        let mut stream: Stream = "a\nb\nc".into();
+        stream.advance();
+        stream.advance();
+        stream.set_line(100);
+        assert_eq!(stream.get(), Ok( Some('b') ));
+        assert_eq!(stream.pos(), Position{ line: 100, column: 1 });
+        stream.advance();
+        assert_eq!(stream.get(), Ok( Some('c') ));
+        assert_eq!(stream.pos(), Position{ line: 101, column: 1 });
+    }
+
+    #[test]
+    fn reset() {
+        let mut stream: Stream = "a\nb".into();
+        assert_eq!(stream.get(), Ok( Some('a') ));
+        assert_eq!(stream.get(), Ok( Some('\n') ));
+        assert_eq!(stream.peek(), Ok( Some('b') ));
+        stream.reset();
+        assert_eq!(stream.pos(), Position{ line: 1, column: 0 });
+        assert_eq!(stream.collect::<Result<String, ()>>(), Ok( "a\nb".to_string() ));
+    }
+
+    #[test]
+    fn from_str() {
+        let mut stream: Stream = "a\u{00f9}".into();
+        assert_eq!(stream.get(), Ok( Some('a') ));
+        assert_eq!(stream.get(), Ok( Some('\u{00f9}') ));
+        assert_eq!(stream.get(), Ok( None ));
+
+        let stream: Stream = String::from("abc").into();
+        assert_eq!(stream.collect::<Result<String, ()>>(), Ok( "abc".to_string() ));
+    }
+
+    #[test]
+    fn resync() {
+        let mut data = b"a".to_vec();
+        data.extend([0xff, 0x80, b'b', 0xc2, b'c', 0xe2, 0x82]);
+        let mut stream = Stream::create(data);
+
+        assert!(!stream.resync());
+        assert_eq!(stream.get(), Ok( Some('a') ));
+        assert_eq!(stream.get(), Err(()));
+        assert!(stream.resync());
+        assert_eq!(stream.get(), Ok( Some('b') ));
+        assert_eq!(stream.pos(), Position{ line: 1, column: 3 });
+        assert_eq!(stream.peek(), Err(()));
+        assert!(stream.resync());
+        assert_eq!(stream.get(), Ok( Some('c') ));
+        assert_eq!(stream.get(), Err(()));
+        assert!(stream.resync());
+        assert_eq!(stream.get(), Ok( None ));
+        assert_eq!(stream.pos(), Position{ line: 1, column: 6 });
+    }
+
+    #[test]
+    fn get_checked() {
+        let mut data = b"ab\nc".to_vec();
+        data.extend([0xe2, 0x82]);
+        data.extend(b"d");
+        data.extend([0x80]);
+        let mut stream = Stream::create(data);
+
+        assert_eq!(stream.get_checked(), Ok( Some('a') ));
+        assert_eq!(stream.get_checked(), Ok( Some('b') ));
+        assert_eq!(stream.get_checked(), Ok( Some('\n') ));
+        assert_eq!(stream.get_checked(), Ok( Some('c') ));
+        assert_eq!(stream.peek(), Err(()));
+        let err = stream.get_checked().unwrap_err();
+        assert_eq!(err, Utf8Error{ byte_offset: 4, position: Position{ line: 2, column: 2 } });
+        assert_eq!(err.to_string(), "invalid UTF-8 at byte 4 (line: 2, column: 2)");
+        assert!(stream.resync());
+        assert_eq!(stream.get_checked(), Ok( Some('d') ));
+        assert_eq!(stream.get_checked(), Err( Utf8Error{ byte_offset: 7, position: Position{ line: 2, column: 4 } } ));
+    }
 
     #[test]
     fn valid_text() {
@@ -202,6 +724,29 @@ mod test {
         assert_eq!(utxt.get().unwrap(), None);
     }
 
+    #[test]
+    fn get_with_pos() {
+        let txt = "ab\n\u{00f9}\n";
+        let mut first: Stream = txt.into();
+        let mut second: Stream = txt.into();
+        let mut previous = second.pos();
+        while let Some((ch, pos)) = first.get_with_pos().unwrap() {
+            assert_eq!(second.get(), Ok( Some(ch) ));
+            match ch {
+                '\n' => assert_eq!(pos, previous.advanced(1)),
+                _ => assert_eq!(pos, second.pos()),
+            }
+            previous = second.pos();
+        }
+        assert_eq!(second.get(), Ok( None ));
+
+        let mut stream: Stream = "a\nb".into();
+        assert_eq!(stream.get_with_pos(), Ok( Some(('a', Position{ line: 1, column: 1 })) ));
+        assert_eq!(stream.get_with_pos(), Ok( Some(('\n', Position{ line: 1, column: 2 })) ));
+        assert_eq!(stream.get_with_pos(), Ok( Some(('b', Position{ line: 2, column: 1 })) ));
+        assert_eq!(stream.get_with_pos(), Ok( None ));
+    }
+
     #[test]
     fn valid_peek() {
         let txt = "a!";
@@ -223,4 +768,31 @@ mod test {
         assert_eq!(utxt.pos(), Position{ line: 1, column: 2});
         assert_eq!(utxt.get().unwrap(), None);
     }
+
+    #[test]
+    fn position_ordering() {
+        let a = Position{ line: 1, column: 12 };
+        let b = Position{ line: 2, column: 1 };
+        let c = Position{ line: 2, column: 3 };
+
+        assert!(a < b);
+        assert!(b < c);
+        assert!(c > a);
+        assert_eq!(b.cmp(&b), std::cmp::Ordering::Equal);
+
+        let mut positions = vec![c, a, b];
+        positions.sort();
+        assert_eq!(positions, vec![a, b, c]);
+    }
+
+    #[test]
+    fn span_contains() {
+        let span = Span::new(Position{ line: 1, column: 5 }, Position{ line: 3, column: 2 });
+
+        assert!(span.contains(Position{ line: 1, column: 5 }));
+        assert!(span.contains(Position{ line: 2, column: 100 }));
+        assert!(span.contains(Position{ line: 3, column: 2 }));
+        assert!(!span.contains(Position{ line: 1, column: 4 }));
+        assert!(!span.contains(Position{ line: 3, column: 3 }));
+    }
 }